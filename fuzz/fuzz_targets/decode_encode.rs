@@ -0,0 +1,117 @@
+//! Differential fuzzing: feed arbitrary bytes to wasmbin's decoder and
+//! cross-check the outcome against `wasmparser`, the reference parser most
+//! of the ecosystem validates against.
+//!
+//! The invariants checked, in order:
+//!
+//! 1. wasmbin and wasmparser must agree on whether the bytes are a valid
+//!    module at all (accept-or-reject the same input).
+//! 2. When both accept, wasmbin's re-encoding of what it decoded must
+//!    *also* validate under wasmparser — a passing decode shouldn't be
+//!    able to produce bytes the reference parser calls invalid.
+//! 3. The decoded shapes (section kinds/counts, function signatures) must
+//!    match, to catch silent divergences that an exact-byte roundtrip
+//!    check misses whenever LEB128 lengths differ between input and
+//!    wasmbin's canonical re-encoding.
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use wasmbin::visit::{Visit, VisitError};
+use wasmbin::Module;
+
+fn unlazify(mut module: Module) -> Result<Module, wasmbin::io::DecodeError> {
+    match module.visit_mut(&mut |_: &mut dyn std::any::Any| Ok::<(), std::convert::Infallible>(())) {
+        Ok(()) => Ok(module),
+        Err(VisitError::LazyDecode(err)) => Err(err),
+        Err(VisitError::Custom(err)) => match err {},
+    }
+}
+
+/// A coarse shape summary used to compare wasmbin's and wasmparser's view
+/// of the same module without needing a type-for-type equality check.
+#[derive(Debug, PartialEq)]
+struct ModuleShape {
+    type_count: usize,
+    func_count: usize,
+    export_count: usize,
+    signatures: Vec<(usize, usize)>,
+}
+
+fn wasmbin_shape(module: &Module) -> ModuleShape {
+    ModuleShape {
+        type_count: module.types.len(),
+        func_count: module.funcs.len(),
+        export_count: module.exports.len(),
+        signatures: module
+            .types
+            .iter()
+            .map(|ty| (ty.params.len(), ty.results.len()))
+            .collect(),
+    }
+}
+
+fn wasmparser_shape(bytes: &[u8]) -> Option<ModuleShape> {
+    let mut type_count = 0;
+    let mut func_count = 0;
+    let mut export_count = 0;
+    let mut signatures = Vec::new();
+
+    for payload in wasmparser::Parser::new(0).parse_all(bytes) {
+        let payload = payload.ok()?;
+        match payload {
+            wasmparser::Payload::TypeSection(reader) => {
+                for ty in reader {
+                    let ty = ty.ok()?;
+                    let wasmparser::Type::Func(func_ty) = ty;
+                    signatures.push((func_ty.params().len(), func_ty.results().len()));
+                    type_count += 1;
+                }
+            }
+            wasmparser::Payload::FunctionSection(reader) => {
+                func_count = reader.get_count() as usize;
+            }
+            wasmparser::Payload::ExportSection(reader) => {
+                export_count = reader.get_count() as usize;
+            }
+            _ => {}
+        }
+    }
+
+    Some(ModuleShape {
+        type_count,
+        func_count,
+        export_count,
+        signatures,
+    })
+}
+
+fuzz_target!(|data: &[u8]| {
+    let wasmbin_result = Module::decode_from(&mut &data[..]).and_then(unlazify);
+    let wasmparser_accepts = wasmparser::Validator::new().validate_all(data).is_ok();
+
+    match (&wasmbin_result, wasmparser_accepts) {
+        (Ok(_), false) => panic!("wasmbin accepted a module wasmparser rejects"),
+        (Err(err), true) => panic!("wasmbin rejected a module wasmparser accepts: {}", err),
+        _ => {}
+    }
+
+    let Ok(module) = wasmbin_result else { return };
+
+    let re_encoded = match module.encode_into(Vec::new()) {
+        Ok(bytes) => bytes,
+        Err(_) => return,
+    };
+
+    if let Err(err) = wasmparser::Validator::new().validate_all(&re_encoded) {
+        panic!("wasmbin re-encoded an accepted module into something wasmparser rejects: {}", err);
+    }
+
+    if let Some(reference_shape) = wasmparser_shape(data) {
+        let our_shape = wasmbin_shape(&module);
+        assert_eq!(
+            our_shape, reference_shape,
+            "wasmbin and wasmparser disagree on the decoded module's shape"
+        );
+    }
+});
@@ -0,0 +1,409 @@
+//! The instruction set: every opcode the decoder understands, grouped the
+//! same way the spec groups them (control flow, variable access, memory,
+//! numeric, and the `0xFC`/`0xFE`-prefixed proposal extensions).
+
+use crate::io::{read_u32_leb128, write_u32_leb128, Decode, DecodeError, Encode, EncodeError, MemArg};
+use crate::types::ValueType;
+use std::io::{Read, Write};
+
+#[cfg(feature = "bulk-memory-operations")]
+pub mod bulk_memory;
+#[cfg(feature = "threads")]
+pub mod threads;
+
+/// A block type: either no result, or a single result value type.
+///
+/// (Multi-value block types exist upstream but aren't needed by anything
+/// this crate currently decodes, so we only model the common case.)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BlockType {
+    Empty,
+    Value(ValueType),
+}
+
+impl Decode for BlockType {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let mut byte = [0u8];
+        r.read_exact(&mut byte)?;
+        Ok(match byte[0] {
+            0x40 => BlockType::Empty,
+            other => BlockType::Value(ValueType::from_byte(other)?),
+        })
+    }
+}
+
+impl Encode for BlockType {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        match self {
+            BlockType::Empty => w.write_all(&[0x40])?,
+            BlockType::Value(ty) => w.write_all(&[ty.to_byte()])?,
+        }
+        Ok(())
+    }
+}
+
+/// A single WebAssembly instruction.
+///
+/// `end`/`else` are not represented here: they're the terminators that
+/// [`decode_body`] stops on and `Instruction::encode` writes after a
+/// `block`/`loop`/`if`'s nested instruction sequence, rather than
+/// instructions in their own right.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Instruction {
+    Unreachable,
+    Nop,
+    Block(BlockType, Vec<Instruction>),
+    Loop(BlockType, Vec<Instruction>),
+    If(BlockType, Vec<Instruction>, Vec<Instruction>),
+    Br(u32),
+    BrIf(u32),
+    BrTable { targets: Vec<u32>, default: u32 },
+    Return,
+    Call(u32),
+    CallIndirect { type_index: u32, table_index: u32 },
+    Drop,
+    Select,
+    LocalGet(u32),
+    LocalSet(u32),
+    LocalTee(u32),
+    GlobalGet(u32),
+    GlobalSet(u32),
+
+    I32Load(MemArg),
+    I64Load(MemArg),
+    F32Load(MemArg),
+    F64Load(MemArg),
+    I32Store(MemArg),
+    I64Store(MemArg),
+    F32Store(MemArg),
+    F64Store(MemArg),
+    MemorySize,
+    MemoryGrow,
+
+    I32Const(i32),
+    I64Const(i64),
+    F32Const(f32),
+    F64Const(f64),
+
+    I32Add,
+    I32Sub,
+    I32Mul,
+
+    /// The full `0xFE`-prefixed threads/atomics family. Kept as its own
+    /// sub-enum so the common, always-available opcode space above stays
+    /// uncluttered by a feature that most builds don't enable.
+    #[cfg(feature = "threads")]
+    Atomic(threads::AtomicInstruction),
+
+    #[cfg(feature = "bulk-memory-operations")]
+    BulkMemory(bulk_memory::BulkMemoryInstruction),
+}
+
+/// What a `block`/`loop`/`if` body (or a function body) can end on.
+enum Terminator {
+    Else,
+    End,
+}
+
+pub(crate) fn decode_instruction_from_opcode(opcode: u8, r: &mut impl Read) -> Result<Instruction, DecodeError> {
+    decode_one(opcode, r)
+}
+
+fn decode_one(opcode: u8, r: &mut impl Read) -> Result<Instruction, DecodeError> {
+    Ok(match opcode {
+        0x00 => Instruction::Unreachable,
+        0x01 => Instruction::Nop,
+        0x02 => {
+            let ty = BlockType::decode(r)?;
+            let (body, _) = decode_body(r)?;
+            Instruction::Block(ty, body)
+        }
+        0x03 => {
+            let ty = BlockType::decode(r)?;
+            let (body, _) = decode_body(r)?;
+            Instruction::Loop(ty, body)
+        }
+        0x04 => {
+            let ty = BlockType::decode(r)?;
+            let (then_body, terminator) = decode_body(r)?;
+            let else_body = match terminator {
+                Terminator::End => Vec::new(),
+                Terminator::Else => decode_body(r)?.0,
+            };
+            Instruction::If(ty, then_body, else_body)
+        }
+        0x0C => Instruction::Br(read_u32_leb128(r)?),
+        0x0D => Instruction::BrIf(read_u32_leb128(r)?),
+        0x0E => {
+            let count = read_u32_leb128(r)?;
+            let mut targets = Vec::with_capacity(count as usize);
+            for _ in 0..count {
+                targets.push(read_u32_leb128(r)?);
+            }
+            let default = read_u32_leb128(r)?;
+            Instruction::BrTable { targets, default }
+        }
+        0x0F => Instruction::Return,
+        0x10 => Instruction::Call(read_u32_leb128(r)?),
+        0x11 => {
+            let type_index = read_u32_leb128(r)?;
+            let table_index = read_u32_leb128(r)?;
+            Instruction::CallIndirect { type_index, table_index }
+        }
+        0x1A => Instruction::Drop,
+        0x1B => Instruction::Select,
+        0x20 => Instruction::LocalGet(read_u32_leb128(r)?),
+        0x21 => Instruction::LocalSet(read_u32_leb128(r)?),
+        0x22 => Instruction::LocalTee(read_u32_leb128(r)?),
+        0x23 => Instruction::GlobalGet(read_u32_leb128(r)?),
+        0x24 => Instruction::GlobalSet(read_u32_leb128(r)?),
+        0x28 => Instruction::I32Load(MemArg::decode(r)?),
+        0x29 => Instruction::I64Load(MemArg::decode(r)?),
+        0x2A => Instruction::F32Load(MemArg::decode(r)?),
+        0x2B => Instruction::F64Load(MemArg::decode(r)?),
+        0x36 => Instruction::I32Store(MemArg::decode(r)?),
+        0x37 => Instruction::I64Store(MemArg::decode(r)?),
+        0x38 => Instruction::F32Store(MemArg::decode(r)?),
+        0x39 => Instruction::F64Store(MemArg::decode(r)?),
+        0x3F => {
+            let mut reserved = [0u8];
+            r.read_exact(&mut reserved)?;
+            Instruction::MemorySize
+        }
+        0x40 => {
+            let mut reserved = [0u8];
+            r.read_exact(&mut reserved)?;
+            Instruction::MemoryGrow
+        }
+        0x41 => Instruction::I32Const(read_u32_leb128(r)? as i32),
+        0x42 => {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf[..1])?;
+            Instruction::I64Const(read_i64_leb128(r, buf[0])?)
+        }
+        0x43 => {
+            let mut buf = [0u8; 4];
+            r.read_exact(&mut buf)?;
+            Instruction::F32Const(f32::from_le_bytes(buf))
+        }
+        0x44 => {
+            let mut buf = [0u8; 8];
+            r.read_exact(&mut buf)?;
+            Instruction::F64Const(f64::from_le_bytes(buf))
+        }
+        0x6A => Instruction::I32Add,
+        0x6B => Instruction::I32Sub,
+        0x6C => Instruction::I32Mul,
+        #[cfg(feature = "threads")]
+        0xFE => Instruction::Atomic(threads::AtomicInstruction::decode(r)?),
+        #[cfg(feature = "bulk-memory-operations")]
+        0xFC => Instruction::BulkMemory(bulk_memory::BulkMemoryInstruction::decode(r)?),
+        other => {
+            return Err(DecodeError::UnexpectedByte {
+                actual: other,
+                expected: &[],
+            })
+        }
+    })
+}
+
+/// Decodes `i64.const`'s signed LEB128 immediate, given its first byte
+/// (which the caller has already read) and a reader positioned after it.
+fn read_i64_leb128(r: &mut impl Read, first_byte: u8) -> Result<i64, DecodeError> {
+    let mut result: i64 = i64::from(first_byte & 0x7F);
+    let mut shift = 7;
+    let mut byte = first_byte;
+    while byte & 0x80 != 0 {
+        let mut next = [0u8];
+        r.read_exact(&mut next)?;
+        byte = next[0];
+        result |= i64::from(byte & 0x7F) << shift;
+        shift += 7;
+    }
+    if shift < 64 && (byte & 0x40) != 0 {
+        result |= -1i64 << shift;
+    }
+    Ok(result)
+}
+
+fn write_i64_leb128(mut value: i64, w: &mut impl Write) -> Result<(), EncodeError> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        let done = (value == 0 && byte & 0x40 == 0) || (value == -1 && byte & 0x40 != 0);
+        if done {
+            w.write_all(&[byte])?;
+            break;
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+    Ok(())
+}
+
+/// Decodes instructions until hitting an `end` or `else` opcode, returning
+/// which one it was so the caller (a `block`/`loop`/`if`, or a function
+/// body) knows whether more follows.
+fn decode_body(r: &mut impl Read) -> Result<(Vec<Instruction>, Terminator), DecodeError> {
+    let mut body = Vec::new();
+    loop {
+        let mut opcode = [0u8];
+        r.read_exact(&mut opcode)?;
+        match opcode[0] {
+            0x0B => return Ok((body, Terminator::End)),
+            0x05 => return Ok((body, Terminator::Else)),
+            other => body.push(decode_one(other, r)?),
+        }
+    }
+}
+
+fn encode_body(body: &[Instruction], w: &mut impl Write) -> Result<(), EncodeError> {
+    for instr in body {
+        instr.encode(w)?;
+    }
+    Ok(())
+}
+
+impl Decode for Instruction {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let mut opcode = [0u8];
+        r.read_exact(&mut opcode)?;
+        decode_one(opcode[0], r)
+    }
+}
+
+impl Encode for Instruction {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        match self {
+            Instruction::Unreachable => w.write_all(&[0x00])?,
+            Instruction::Nop => w.write_all(&[0x01])?,
+            Instruction::Block(ty, body) => {
+                w.write_all(&[0x02])?;
+                ty.encode(w)?;
+                encode_body(body, w)?;
+                w.write_all(&[0x0B])?;
+            }
+            Instruction::Loop(ty, body) => {
+                w.write_all(&[0x03])?;
+                ty.encode(w)?;
+                encode_body(body, w)?;
+                w.write_all(&[0x0B])?;
+            }
+            Instruction::If(ty, then_body, else_body) => {
+                w.write_all(&[0x04])?;
+                ty.encode(w)?;
+                encode_body(then_body, w)?;
+                if !else_body.is_empty() {
+                    w.write_all(&[0x05])?;
+                    encode_body(else_body, w)?;
+                }
+                w.write_all(&[0x0B])?;
+            }
+            Instruction::Br(label) => {
+                w.write_all(&[0x0C])?;
+                write_u32_leb128(*label, w)?;
+            }
+            Instruction::BrIf(label) => {
+                w.write_all(&[0x0D])?;
+                write_u32_leb128(*label, w)?;
+            }
+            Instruction::BrTable { targets, default } => {
+                w.write_all(&[0x0E])?;
+                write_u32_leb128(targets.len() as u32, w)?;
+                for target in targets {
+                    write_u32_leb128(*target, w)?;
+                }
+                write_u32_leb128(*default, w)?;
+            }
+            Instruction::Return => w.write_all(&[0x0F])?,
+            Instruction::Call(index) => {
+                w.write_all(&[0x10])?;
+                write_u32_leb128(*index, w)?;
+            }
+            Instruction::CallIndirect { type_index, table_index } => {
+                w.write_all(&[0x11])?;
+                write_u32_leb128(*type_index, w)?;
+                write_u32_leb128(*table_index, w)?;
+            }
+            Instruction::Drop => w.write_all(&[0x1A])?,
+            Instruction::Select => w.write_all(&[0x1B])?,
+            Instruction::LocalGet(i) => {
+                w.write_all(&[0x20])?;
+                write_u32_leb128(*i, w)?;
+            }
+            Instruction::LocalSet(i) => {
+                w.write_all(&[0x21])?;
+                write_u32_leb128(*i, w)?;
+            }
+            Instruction::LocalTee(i) => {
+                w.write_all(&[0x22])?;
+                write_u32_leb128(*i, w)?;
+            }
+            Instruction::GlobalGet(i) => {
+                w.write_all(&[0x23])?;
+                write_u32_leb128(*i, w)?;
+            }
+            Instruction::GlobalSet(i) => {
+                w.write_all(&[0x24])?;
+                write_u32_leb128(*i, w)?;
+            }
+            Instruction::I32Load(m) => {
+                w.write_all(&[0x28])?;
+                m.encode(w)?;
+            }
+            Instruction::I64Load(m) => {
+                w.write_all(&[0x29])?;
+                m.encode(w)?;
+            }
+            Instruction::F32Load(m) => {
+                w.write_all(&[0x2A])?;
+                m.encode(w)?;
+            }
+            Instruction::F64Load(m) => {
+                w.write_all(&[0x2B])?;
+                m.encode(w)?;
+            }
+            Instruction::I32Store(m) => {
+                w.write_all(&[0x36])?;
+                m.encode(w)?;
+            }
+            Instruction::I64Store(m) => {
+                w.write_all(&[0x37])?;
+                m.encode(w)?;
+            }
+            Instruction::F32Store(m) => {
+                w.write_all(&[0x38])?;
+                m.encode(w)?;
+            }
+            Instruction::F64Store(m) => {
+                w.write_all(&[0x39])?;
+                m.encode(w)?;
+            }
+            Instruction::MemorySize => w.write_all(&[0x3F, 0x00])?,
+            Instruction::MemoryGrow => w.write_all(&[0x40, 0x00])?,
+            Instruction::I32Const(v) => {
+                w.write_all(&[0x41])?;
+                write_u32_leb128(*v as u32, w)?;
+            }
+            Instruction::I64Const(v) => {
+                w.write_all(&[0x42])?;
+                write_i64_leb128(*v, w)?;
+            }
+            Instruction::F32Const(v) => {
+                w.write_all(&[0x43])?;
+                w.write_all(&v.to_le_bytes())?;
+            }
+            Instruction::F64Const(v) => {
+                w.write_all(&[0x44])?;
+                w.write_all(&v.to_le_bytes())?;
+            }
+            Instruction::I32Add => w.write_all(&[0x6A])?,
+            Instruction::I32Sub => w.write_all(&[0x6B])?,
+            Instruction::I32Mul => w.write_all(&[0x6C])?,
+            #[cfg(feature = "threads")]
+            Instruction::Atomic(atomic) => atomic.encode(w)?,
+            #[cfg(feature = "bulk-memory-operations")]
+            Instruction::BulkMemory(bulk_memory) => bulk_memory.encode(w)?,
+        }
+        Ok(())
+    }
+}
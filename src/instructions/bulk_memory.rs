@@ -0,0 +1,68 @@
+//! The `0xFC`-prefixed bulk-memory instructions. Only the two operations
+//! [`Module::validate`](crate::module::Module::validate) actually needs to
+//! reason about — `memory.init` and `data.drop` — are modeled so far, since
+//! they're what makes the data count section mandatory.
+
+use crate::io::{read_u32_leb128, write_u32_leb128, Decode, DecodeError, Encode, EncodeError};
+use std::io::{Read, Write};
+
+/// A bulk-memory instruction that references a data segment by index.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BulkMemoryInstruction {
+    /// `memory.init $data`: copy from a passive data segment into memory.
+    MemoryInit { data_index: u32 },
+    /// `data.drop $data`: discard a passive data segment.
+    DataDrop { data_index: u32 },
+}
+
+impl BulkMemoryInstruction {
+    /// The data segment this instruction references, used by validation to
+    /// decide whether a data count section is required.
+    pub fn data_index(&self) -> u32 {
+        match *self {
+            BulkMemoryInstruction::MemoryInit { data_index } => data_index,
+            BulkMemoryInstruction::DataDrop { data_index } => data_index,
+        }
+    }
+}
+
+impl Decode for BulkMemoryInstruction {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let subopcode = read_u32_leb128(r)?;
+        Ok(match subopcode {
+            0x08 => {
+                let data_index = read_u32_leb128(r)?;
+                let mut mem_index = [0u8];
+                r.read_exact(&mut mem_index)?;
+                BulkMemoryInstruction::MemoryInit { data_index }
+            }
+            0x09 => BulkMemoryInstruction::DataDrop {
+                data_index: read_u32_leb128(r)?,
+            },
+            other => {
+                return Err(DecodeError::UnexpectedByte {
+                    actual: other as u8,
+                    expected: &[0x08, 0x09],
+                })
+            }
+        })
+    }
+}
+
+impl Encode for BulkMemoryInstruction {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        w.write_all(&[0xFC])?;
+        match *self {
+            BulkMemoryInstruction::MemoryInit { data_index } => {
+                write_u32_leb128(0x08, w)?;
+                write_u32_leb128(data_index, w)?;
+                w.write_all(&[0x00])?;
+            }
+            BulkMemoryInstruction::DataDrop { data_index } => {
+                write_u32_leb128(0x09, w)?;
+                write_u32_leb128(data_index, w)?;
+            }
+        }
+        Ok(())
+    }
+}
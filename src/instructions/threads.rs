@@ -0,0 +1,256 @@
+//! The `0xFE`-prefixed atomic memory instructions added by the threads
+//! proposal: `atomic.notify`/`memory.atomic.wait*`, `atomic.fence`, and the
+//! atomic `load`/`store`/read-modify-write family.
+//!
+//! Rather than one enum variant per opcode (there are 67 of them once
+//! every size-suffixed RMW op is counted), instructions are grouped by shape
+//! the same way the spec's opcode table does: a width (`i32`/`i64`), an
+//! optional narrowing to a sub-word access (`8`/`16`, plus `32` for `i64`),
+//! and — for read-modify-write — which operation to perform.
+
+use crate::io::{Decode, DecodeError, Encode, EncodeError, MemArg};
+use std::io::{Read, Write};
+
+/// The full-width operand type an atomic instruction operates on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Width {
+    I32,
+    I64,
+}
+
+/// When present, the access is narrowed to fewer bits than `Width` implies
+/// (e.g. `i64.atomic.load8_u` loads 8 bits and zero-extends to `i64`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Narrow {
+    W8,
+    W16,
+    /// Only valid together with [`Width::I64`] (narrowing an `i64` access to
+    /// the same 32 bits an `i32` access would touch).
+    W32,
+}
+
+/// Which read-modify-write operation to perform; the memory word is read,
+/// combined with the instruction's stack operand via this op, the result is
+/// written back, and the *original* value is left on the stack.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum RmwOp {
+    Add,
+    Sub,
+    And,
+    Or,
+    Xor,
+    Xchg,
+    Cmpxchg,
+}
+
+/// A single threads-proposal atomic instruction.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AtomicInstruction {
+    /// `memory.atomic.notify`: wake up to `count` agents waiting on the
+    /// given address.
+    Notify(MemArg),
+    /// `memory.atomic.wait32`: suspend the current agent until notified, an
+    /// optional timeout elapses, or the expected `i32` value no longer
+    /// matches.
+    Wait32(MemArg),
+    /// `memory.atomic.wait64`: as [`Self::Wait32`], comparing an `i64`.
+    Wait64(MemArg),
+    /// `atomic.fence`: a memory fence with no address operand, just a
+    /// reserved zero byte.
+    Fence,
+    Load {
+        width: Width,
+        narrow: Option<Narrow>,
+        arg: MemArg,
+    },
+    Store {
+        width: Width,
+        narrow: Option<Narrow>,
+        arg: MemArg,
+    },
+    Rmw {
+        op: RmwOp,
+        width: Width,
+        narrow: Option<Narrow>,
+        arg: MemArg,
+    },
+}
+
+/// `(width, narrow)` pairs in the order the spec lays out each
+/// load/store/RMW group of seven: `i32`, `i64`, `i32_8`, `i32_16`, `i64_8`,
+/// `i64_16`, `i64_32`.
+const ACCESS_SHAPES: [(Width, Option<Narrow>); 7] = [
+    (Width::I32, None),
+    (Width::I64, None),
+    (Width::I32, Some(Narrow::W8)),
+    (Width::I32, Some(Narrow::W16)),
+    (Width::I64, Some(Narrow::W8)),
+    (Width::I64, Some(Narrow::W16)),
+    (Width::I64, Some(Narrow::W32)),
+];
+
+fn shape_index(width: Width, narrow: Option<Narrow>) -> usize {
+    ACCESS_SHAPES
+        .iter()
+        .position(|&(w, n)| w == width && n == narrow)
+        .expect("not a valid atomic access shape")
+}
+
+impl Decode for AtomicInstruction {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let mut byte = [0u8];
+        r.read_exact(&mut byte)?;
+        let subopcode = byte[0];
+        Ok(match subopcode {
+            0x00 => AtomicInstruction::Notify(MemArg::decode(r)?),
+            0x01 => AtomicInstruction::Wait32(MemArg::decode(r)?),
+            0x02 => AtomicInstruction::Wait64(MemArg::decode(r)?),
+            0x03 => {
+                let mut reserved = [0u8];
+                r.read_exact(&mut reserved)?;
+                AtomicInstruction::Fence
+            }
+            0x10..=0x16 => {
+                let (width, narrow) = ACCESS_SHAPES[(subopcode - 0x10) as usize];
+                AtomicInstruction::Load {
+                    width,
+                    narrow,
+                    arg: MemArg::decode(r)?,
+                }
+            }
+            0x17..=0x1D => {
+                let (width, narrow) = ACCESS_SHAPES[(subopcode - 0x17) as usize];
+                AtomicInstruction::Store {
+                    width,
+                    narrow,
+                    arg: MemArg::decode(r)?,
+                }
+            }
+            0x1E..=0x4E => {
+                let offset = subopcode - 0x1E;
+                let op_index = offset / 7;
+                let shape_index = (offset % 7) as usize;
+                let op = [
+                    RmwOp::Add,
+                    RmwOp::Sub,
+                    RmwOp::And,
+                    RmwOp::Or,
+                    RmwOp::Xor,
+                    RmwOp::Xchg,
+                    RmwOp::Cmpxchg,
+                ][op_index as usize];
+                let (width, narrow) = ACCESS_SHAPES[shape_index];
+                AtomicInstruction::Rmw {
+                    op,
+                    width,
+                    narrow,
+                    arg: MemArg::decode(r)?,
+                }
+            }
+            other => {
+                return Err(DecodeError::UnexpectedByte {
+                    actual: other,
+                    expected: &[],
+                })
+            }
+        })
+    }
+}
+
+impl Encode for AtomicInstruction {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        w.write_all(&[0xFE])?;
+        match *self {
+            AtomicInstruction::Notify(arg) => {
+                w.write_all(&[0x00])?;
+                arg.encode(w)?;
+            }
+            AtomicInstruction::Wait32(arg) => {
+                w.write_all(&[0x01])?;
+                arg.encode(w)?;
+            }
+            AtomicInstruction::Wait64(arg) => {
+                w.write_all(&[0x02])?;
+                arg.encode(w)?;
+            }
+            AtomicInstruction::Fence => {
+                w.write_all(&[0x03, 0x00])?;
+            }
+            AtomicInstruction::Load { width, narrow, arg } => {
+                w.write_all(&[0x10 + shape_index(width, narrow) as u8])?;
+                arg.encode(w)?;
+            }
+            AtomicInstruction::Store { width, narrow, arg } => {
+                w.write_all(&[0x17 + shape_index(width, narrow) as u8])?;
+                arg.encode(w)?;
+            }
+            AtomicInstruction::Rmw { op, width, narrow, arg } => {
+                let op_index = match op {
+                    RmwOp::Add => 0,
+                    RmwOp::Sub => 1,
+                    RmwOp::And => 2,
+                    RmwOp::Or => 3,
+                    RmwOp::Xor => 4,
+                    RmwOp::Xchg => 5,
+                    RmwOp::Cmpxchg => 6,
+                };
+                let opcode = 0x1E + op_index * 7 + shape_index(width, narrow) as u8;
+                w.write_all(&[opcode])?;
+                arg.encode(w)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip(instr: AtomicInstruction) {
+        let mut bytes = Vec::new();
+        instr.encode(&mut bytes).unwrap();
+        // Strip the shared 0xFE prefix that `Instruction::decode` would
+        // normally consume before dispatching here.
+        let mut rest = &bytes[1..];
+        let decoded = AtomicInstruction::decode(&mut rest).unwrap();
+        assert_eq!(instr, decoded);
+        assert!(rest.is_empty());
+    }
+
+    #[test]
+    fn roundtrips_notify_and_wait() {
+        let arg = MemArg { align: 2, offset: 4 };
+        roundtrip(AtomicInstruction::Notify(arg));
+        roundtrip(AtomicInstruction::Wait32(arg));
+        roundtrip(AtomicInstruction::Wait64(arg));
+        roundtrip(AtomicInstruction::Fence);
+    }
+
+    #[test]
+    fn roundtrips_every_load_store_shape() {
+        let arg = MemArg { align: 0, offset: 0 };
+        for &(width, narrow) in &ACCESS_SHAPES {
+            roundtrip(AtomicInstruction::Load { width, narrow, arg });
+            roundtrip(AtomicInstruction::Store { width, narrow, arg });
+        }
+    }
+
+    #[test]
+    fn roundtrips_every_rmw_op_and_shape() {
+        let arg = MemArg { align: 0, offset: 0 };
+        for &op in &[
+            RmwOp::Add,
+            RmwOp::Sub,
+            RmwOp::And,
+            RmwOp::Or,
+            RmwOp::Xor,
+            RmwOp::Xchg,
+            RmwOp::Cmpxchg,
+        ] {
+            for &(width, narrow) in &ACCESS_SHAPES {
+                roundtrip(AtomicInstruction::Rmw { op, width, narrow, arg });
+            }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Low-level primitives for decoding and encoding the WebAssembly binary format.
+//!
+//! Every other module in the crate builds on the [`Decode`]/[`Encode`] traits
+//! defined here: each wasmbin type knows how to read itself from a byte
+//! stream and write itself back out, and composite types (sections, the
+//! [`Module`](crate::Module) itself) simply delegate to their fields.
+
+use std::convert::TryFrom;
+use std::io::{self, Read, Write};
+use thiserror::Error;
+
+/// Everything that can go wrong while decoding a module from bytes.
+#[derive(Debug, Error)]
+pub enum DecodeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("unexpected byte {actual:#X}, expected one of {expected:?}")]
+    UnexpectedByte { actual: u8, expected: &'static [u8] },
+
+    #[error("LEB128 value out of range for target type")]
+    LebOutOfRange,
+
+    #[error("invalid UTF-8 in a name: {0}")]
+    InvalidUtf8(#[from] std::string::FromUtf8Error),
+}
+
+/// Everything that can go wrong while encoding a module back to bytes.
+#[derive(Debug, Error)]
+pub enum EncodeError {
+    #[error("I/O error: {0}")]
+    Io(#[from] io::Error),
+}
+
+/// A type that can be read from a WebAssembly byte stream.
+pub trait Decode: Sized {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError>;
+}
+
+/// A type that can be written out in WebAssembly binary form.
+pub trait Encode {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError>;
+}
+
+fn read_u8(r: &mut impl Read) -> Result<u8, DecodeError> {
+    let mut byte = [0u8];
+    r.read_exact(&mut byte)?;
+    Ok(byte[0])
+}
+
+/// Reads an unsigned LEB128-encoded `u32`, as used for most indices and counts.
+pub fn read_u32_leb128(r: &mut impl Read) -> Result<u32, DecodeError> {
+    let mut result: u64 = 0;
+    let mut shift = 0;
+    loop {
+        let byte = read_u8(r)?;
+        result |= u64::from(byte & 0x7F) << shift;
+        if byte & 0x80 == 0 {
+            break;
+        }
+        shift += 7;
+        if shift > 35 {
+            return Err(DecodeError::LebOutOfRange);
+        }
+    }
+    u32::try_from(result).map_err(|_| DecodeError::LebOutOfRange)
+}
+
+/// Writes an unsigned LEB128-encoded `u32`.
+pub fn write_u32_leb128(mut value: u32, w: &mut impl Write) -> Result<(), EncodeError> {
+    loop {
+        let byte = (value & 0x7F) as u8;
+        value >>= 7;
+        if value == 0 {
+            w.write_all(&[byte])?;
+            break;
+        }
+        w.write_all(&[byte | 0x80])?;
+    }
+    Ok(())
+}
+
+impl Decode for u32 {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        read_u32_leb128(r)
+    }
+}
+
+impl Encode for u32 {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        write_u32_leb128(*self, w)
+    }
+}
+
+/// The immediate operand shared by every memory-accessing instruction
+/// (`load`/`store` and their atomic counterparts): an alignment hint
+/// followed by a byte offset, both encoded as unsigned LEB128.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MemArg {
+    pub align: u32,
+    pub offset: u32,
+}
+
+impl Decode for MemArg {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        Ok(MemArg {
+            align: read_u32_leb128(r)?,
+            offset: read_u32_leb128(r)?,
+        })
+    }
+}
+
+impl Encode for MemArg {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        write_u32_leb128(self.align, w)?;
+        write_u32_leb128(self.offset, w)
+    }
+}
@@ -0,0 +1,33 @@
+// Copyright 2020 Google Inc. All Rights Reserved.
+//
+// Licensed under the Apache License, Version 2.0 (the "License");
+// you may not use this file except in compliance with the License.
+// You may obtain a copy of the License at
+//
+//     http://www.apache.org/licenses/LICENSE-2.0
+//
+// Unless required by applicable law or agreed to in writing, software
+// distributed under the License is distributed on an "AS IS" BASIS,
+// WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+// See the License for the specific language governing permissions and
+// limitations under the License.
+
+//! A low-level, lossless codec for the WebAssembly binary format.
+//!
+//! wasmbin decodes a `.wasm` file into a [`Module`] tree and can re-encode
+//! it without dropping content: sections it doesn't model are kept as raw
+//! bytes. Decoding is eager, so the whole tree is built up front (see
+//! [`visit`]).
+
+pub mod instructions;
+pub mod io;
+pub mod module;
+pub mod pass;
+pub mod types;
+pub mod validate;
+pub mod visit;
+pub mod wat;
+
+pub use module::Module;
+pub use validate::ValidationError;
+pub use wat::WatParseError;
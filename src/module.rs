@@ -0,0 +1,399 @@
+//! The top-level [`Module`] type and the section list it's built from.
+
+use crate::instructions::Instruction;
+use crate::io::{read_u32_leb128, write_u32_leb128, Decode, DecodeError, Encode, EncodeError};
+use crate::types::{FuncType, ValueType};
+use std::io::{self, Read, Write};
+
+const MAGIC: [u8; 4] = [0x00, b'a', b's', b'm'];
+const VERSION: [u8; 4] = [0x01, 0x00, 0x00, 0x00];
+
+/// A function's local declarations followed by its instruction sequence.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct FuncBody {
+    pub locals: Vec<ValueType>,
+    pub instructions: Vec<Instruction>,
+}
+
+/// A single exported name, pointing at a function by index.
+///
+/// (Table/memory/global exports exist upstream too; only function exports
+/// are modeled here since nothing in the crate yet needs the others.)
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Export {
+    pub name: String,
+    pub func_index: u32,
+}
+
+/// A custom section (id 0): a name plus an opaque payload that the spec
+/// doesn't assign any meaning to. Order relative to the standard sections
+/// isn't preserved — they're re-emitted right after the header on encode.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct CustomSection {
+    pub name: String,
+    pub payload: Vec<u8>,
+}
+
+/// A standard (non-custom) section whose id isn't modeled by [`Module`] yet
+/// (import, table, memory, global, start, element, data, ...). Kept as raw
+/// bytes purely so `encode_into` stays lossless for modules that use them,
+/// the same way [`CustomSection`] preserves content this crate doesn't
+/// otherwise understand — decoding never drops section content on the
+/// floor, it just declines to interpret some of it.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct RawSection {
+    pub id: u8,
+    pub payload: Vec<u8>,
+}
+
+/// A fully decoded WebAssembly module: the in-memory tree every other part
+/// of the crate reads from and writes back to.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Module {
+    pub custom_sections: Vec<CustomSection>,
+    pub types: Vec<FuncType>,
+    /// Each function's index into `types`, in declaration order; `code[i]`
+    /// is that function's body.
+    pub funcs: Vec<u32>,
+    pub code: Vec<FuncBody>,
+    pub exports: Vec<Export>,
+    /// The number of data segments, from the data count section (id 12).
+    /// Required (and checked by [`crate::validate`]) whenever a function
+    /// references a data segment by index before the data section itself
+    /// has been decoded.
+    pub data_count: Option<u32>,
+    /// Sections with a standard (non-custom) id that [`Module`] doesn't
+    /// model yet, preserved verbatim so round-tripping a module that uses
+    /// them doesn't silently drop content. See [`RawSection`].
+    pub other_sections: Vec<RawSection>,
+}
+
+impl Module {
+    pub fn decode_from(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let mut magic = [0u8; 4];
+        r.read_exact(&mut magic)?;
+        if magic != MAGIC {
+            return Err(DecodeError::UnexpectedByte {
+                actual: magic[0],
+                expected: &[0x00],
+            });
+        }
+        let mut version = [0u8; 4];
+        r.read_exact(&mut version)?;
+        if version != VERSION {
+            return Err(DecodeError::UnexpectedByte {
+                actual: version[0],
+                expected: &[0x01],
+            });
+        }
+
+        let mut module = Module::default();
+        loop {
+            let mut id = [0u8];
+            if r.read(&mut id)? == 0 {
+                break;
+            }
+            let size = read_u32_leb128(r)?;
+            let section_bytes = read_bounded(r, size)?;
+            let mut section_r = section_bytes.as_slice();
+            match id[0] {
+                0 => module.custom_sections.push(CustomSection::decode(&mut section_r)?),
+                1 => module.types = decode_vec(&mut section_r, FuncType::decode)?,
+                3 => module.funcs = decode_vec(&mut section_r, u32::decode)?,
+                7 => module.exports = decode_vec(&mut section_r, Export::decode)?,
+                10 => module.code = decode_vec(&mut section_r, FuncBody::decode)?,
+                12 => module.data_count = Some(read_u32_leb128(&mut section_r)?),
+                other => module.other_sections.push(RawSection { id: other, payload: section_bytes }),
+            }
+        }
+        Ok(module)
+    }
+
+    pub fn encode_into<W: Write>(&self, mut w: W) -> Result<W, EncodeError> {
+        w.write_all(&MAGIC)?;
+        w.write_all(&VERSION)?;
+
+        for custom in &self.custom_sections {
+            write_section(&mut w, 0, |body| custom.encode(body))?;
+        }
+
+        // Standard sections must appear in this order (custom sections
+        // aside, which can go anywhere and are emitted up front above); sort
+        // the sections we know how to write alongside any raw ones we're
+        // just round-tripping so the result still respects it.
+        let mut sections: Vec<(u8, Vec<u8>)> = Vec::new();
+        if !self.types.is_empty() {
+            sections.push((1, section_body(|body| encode_vec(&self.types, body))?));
+        }
+        if !self.funcs.is_empty() {
+            sections.push((3, section_body(|body| encode_vec(&self.funcs, body))?));
+        }
+        if !self.exports.is_empty() {
+            sections.push((7, section_body(|body| encode_vec(&self.exports, body))?));
+        }
+        if let Some(data_count) = self.data_count {
+            sections.push((12, section_body(|body| write_u32_leb128(data_count, body))?));
+        }
+        if !self.code.is_empty() {
+            sections.push((10, section_body(|body| encode_vec(&self.code, body))?));
+        }
+        for raw in &self.other_sections {
+            sections.push((raw.id, raw.payload.clone()));
+        }
+        sections.sort_by_key(|&(id, _)| section_order(id));
+
+        for (id, body) in sections {
+            write_section(&mut w, id, |out| Ok(out.write_all(&body)?))?;
+        }
+        Ok(w)
+    }
+}
+
+/// Where a standard section id sits in the order the spec requires sections
+/// to appear in (custom sections are handled separately and can go
+/// anywhere).
+fn section_order(id: u8) -> usize {
+    const ORDER: [u8; 12] = [1, 2, 3, 4, 5, 6, 7, 8, 9, 12, 10, 11];
+    ORDER.iter().position(|&x| x == id).unwrap_or(ORDER.len())
+}
+
+fn write_section<W: Write>(w: &mut W, id: u8, encode_body: impl FnOnce(&mut Vec<u8>) -> Result<(), EncodeError>) -> Result<(), EncodeError> {
+    let body = section_body(encode_body)?;
+    w.write_all(&[id])?;
+    write_u32_leb128(body.len() as u32, w)?;
+    Ok(w.write_all(&body)?)
+}
+
+fn section_body(encode_body: impl FnOnce(&mut Vec<u8>) -> Result<(), EncodeError>) -> Result<Vec<u8>, EncodeError> {
+    let mut body = Vec::new();
+    encode_body(&mut body)?;
+    Ok(body)
+}
+
+/// Reads exactly `len` bytes, the way `read_exact` into a pre-sized buffer
+/// would — but without trusting `len` enough to allocate it up front.
+/// `len` comes straight off the wire as an attacker-controlled LEB128
+/// value, and a handful of bytes claiming a multi-gigabyte section would
+/// otherwise force an allocation of that size before the short read ever
+/// fails. `Read::take` caps how far `read_to_end` will grow the buffer, so
+/// it never allocates more than what the underlying reader actually had to
+/// give.
+fn read_bounded(r: &mut impl Read, len: u32) -> Result<Vec<u8>, DecodeError> {
+    let mut buf = Vec::new();
+    r.take(u64::from(len)).read_to_end(&mut buf)?;
+    if buf.len() != len as usize {
+        return Err(DecodeError::Io(io::Error::new(
+            io::ErrorKind::UnexpectedEof,
+            "declared length ran past the end of the input",
+        )));
+    }
+    Ok(buf)
+}
+
+
+fn encode_vec<T: Encode>(items: &[T], w: &mut impl Write) -> Result<(), EncodeError> {
+    write_u32_leb128(items.len() as u32, w)?;
+    for item in items {
+        item.encode(w)?;
+    }
+    Ok(())
+}
+
+impl Decode for CustomSection {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let len = read_u32_leb128(r)?;
+        let name_bytes = read_bounded(r, len)?;
+        let name = String::from_utf8(name_bytes)?;
+        let mut payload = Vec::new();
+        r.read_to_end(&mut payload)?;
+        Ok(CustomSection { name, payload })
+    }
+}
+
+impl Encode for CustomSection {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        write_u32_leb128(self.name.len() as u32, w)?;
+        w.write_all(self.name.as_bytes())?;
+        Ok(w.write_all(&self.payload)?)
+    }
+}
+
+fn decode_vec<T, R: Read>(r: &mut R, mut decode_one: impl FnMut(&mut R) -> Result<T, DecodeError>) -> Result<Vec<T>, DecodeError> {
+    let count = read_u32_leb128(r)?;
+    let mut out = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        out.push(decode_one(r)?);
+    }
+    Ok(out)
+}
+
+impl Decode for FuncType {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let mut form = [0u8];
+        r.read_exact(&mut form)?;
+        let param_count = read_u32_leb128(r)?;
+        let mut params = Vec::with_capacity(param_count as usize);
+        for _ in 0..param_count {
+            let mut byte = [0u8];
+            r.read_exact(&mut byte)?;
+            params.push(ValueType::from_byte(byte[0])?);
+        }
+        let result_count = read_u32_leb128(r)?;
+        let mut results = Vec::with_capacity(result_count as usize);
+        for _ in 0..result_count {
+            let mut byte = [0u8];
+            r.read_exact(&mut byte)?;
+            results.push(ValueType::from_byte(byte[0])?);
+        }
+        Ok(FuncType { params, results })
+    }
+}
+
+impl Encode for FuncType {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        w.write_all(&[0x60])?;
+        write_u32_leb128(self.params.len() as u32, w)?;
+        for ty in &self.params {
+            w.write_all(&[ty.to_byte()])?;
+        }
+        write_u32_leb128(self.results.len() as u32, w)?;
+        for ty in &self.results {
+            w.write_all(&[ty.to_byte()])?;
+        }
+        Ok(())
+    }
+}
+
+impl Decode for Export {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let len = read_u32_leb128(r)?;
+        let name_bytes = read_bounded(r, len)?;
+        let name = String::from_utf8(name_bytes).map_err(DecodeError::from)?;
+        let mut kind = [0u8];
+        r.read_exact(&mut kind)?;
+        let func_index = read_u32_leb128(r)?;
+        Ok(Export { name, func_index })
+    }
+}
+
+impl Encode for Export {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        write_u32_leb128(self.name.len() as u32, w)?;
+        w.write_all(self.name.as_bytes())?;
+        w.write_all(&[0x00])?;
+        write_u32_leb128(self.func_index, w)
+    }
+}
+
+impl Decode for FuncBody {
+    fn decode(r: &mut impl Read) -> Result<Self, DecodeError> {
+        let size = read_u32_leb128(r)?;
+        let body_bytes = read_bounded(r, size)?;
+        let mut body_r = body_bytes.as_slice();
+
+        let local_group_count = read_u32_leb128(&mut body_r)?;
+        let mut locals = Vec::new();
+        for _ in 0..local_group_count {
+            let count = read_u32_leb128(&mut body_r)?;
+            let mut byte = [0u8];
+            body_r.read_exact(&mut byte)?;
+            let ty = ValueType::from_byte(byte[0])?;
+            locals.extend(std::iter::repeat_n(ty, count as usize));
+        }
+
+        let mut instructions = Vec::new();
+        loop {
+            let mut opcode = [0u8];
+            if body_r.read(&mut opcode)? == 0 {
+                break;
+            }
+            if opcode[0] == 0x0B {
+                break;
+            }
+            instructions.push(crate::instructions::decode_instruction_from_opcode(opcode[0], &mut body_r)?);
+        }
+
+        Ok(FuncBody { locals, instructions })
+    }
+}
+
+impl Encode for FuncBody {
+    fn encode(&self, w: &mut impl Write) -> Result<(), EncodeError> {
+        let mut body = Vec::new();
+        // Run-length encode consecutive same-typed locals into declaration
+        // groups, the way the spec requires — writing a single group for
+        // the whole list (assuming one type) would corrupt any function
+        // whose locals aren't all the same type.
+        let mut groups: Vec<(u32, ValueType)> = Vec::new();
+        for &ty in &self.locals {
+            match groups.last_mut() {
+                Some((count, last_ty)) if *last_ty == ty => *count += 1,
+                _ => groups.push((1, ty)),
+            }
+        }
+        write_u32_leb128(groups.len() as u32, &mut body)?;
+        for (count, ty) in groups {
+            write_u32_leb128(count, &mut body)?;
+            body.write_all(&[ty.to_byte()])?;
+        }
+        for instr in &self.instructions {
+            instr.encode(&mut body)?;
+        }
+        body.write_all(&[0x0B])?;
+        write_u32_leb128(body.len() as u32, w)?;
+        Ok(w.write_all(&body)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn roundtrips_locals_of_more_than_one_type() {
+        let body = FuncBody {
+            locals: vec![ValueType::I32, ValueType::I64, ValueType::F32],
+            instructions: Vec::new(),
+        };
+        let mut bytes = Vec::new();
+        body.encode(&mut bytes).unwrap();
+        let decoded = FuncBody::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn groups_consecutive_locals_of_the_same_type_together() {
+        let body = FuncBody {
+            locals: vec![ValueType::I32, ValueType::I32, ValueType::I64],
+            instructions: Vec::new(),
+        };
+        let mut bytes = Vec::new();
+        body.encode(&mut bytes).unwrap();
+        // Skip the outer body-length prefix to read the local-group count:
+        // two groups (2x i32, 1x i64), not three.
+        assert_eq!(bytes[1], 2);
+        let decoded = FuncBody::decode(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, body);
+    }
+
+    #[test]
+    fn roundtrips_sections_it_does_not_model() {
+        let mut module = Module::default();
+        module.other_sections.push(RawSection { id: 5, payload: vec![0x01, 0x02, 0x03] });
+        let bytes = module.encode_into(Vec::new()).unwrap();
+        let decoded = Module::decode_from(&mut bytes.as_slice()).unwrap();
+        assert_eq!(decoded, module);
+    }
+
+    #[test]
+    fn rejects_a_section_whose_declared_length_overruns_the_input() {
+        // Section id 1 (type), declared length 0xFFFFFFFF, but no bytes
+        // actually follow: must fail, not allocate 4GB.
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend_from_slice(&VERSION);
+        bytes.push(1);
+        bytes.extend_from_slice(&[0xFF, 0xFF, 0xFF, 0xFF, 0x0F]);
+        assert!(Module::decode_from(&mut bytes.as_slice()).is_err());
+    }
+
+}
@@ -0,0 +1,195 @@
+//! A binaryen-style pass framework built directly on [`crate::visit`]:
+//! callers register handlers keyed by node type and run them over a module
+//! in a single [`Visit::visit_mut`] traversal, mutating nodes in place as
+//! they go.
+//!
+//! ```ignore
+//! Pass::new()
+//!     .on::<Export>(|export| export.name.make_ascii_lowercase())
+//!     .run(&mut module);
+//! ```
+//!
+//! A handler sees `&mut T` for whichever node type it registered for and
+//! can edit it freely; replacing a node's value wholesale (`*node = ...`)
+//! is how a pass "replaces" or effectively "deletes" it (by substituting a
+//! no-op, for node kinds where one exists). Passes that need to actually
+//! remove entries from a sequence — constant folding collapsing three
+//! instructions into one, for example — operate one level up, on the
+//! `Vec` or struct that owns that sequence, where `Vec::retain`/splicing
+//! is available.
+//!
+//! `Pass` itself does no tree-walking: [`Pass::run`] just drives
+//! `Module::visit_mut`, matching each visited node's `TypeId` against its
+//! registered handlers. Once `Module`'s decode path starts deferring work
+//! lazily, that forcing happens inside `visit_mut` and every existing pass
+//! picks it up automatically, with no changes here.
+
+use crate::instructions::Instruction;
+use crate::module::{Export, FuncBody, Module};
+use crate::visit::Visit;
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+/// A single type-keyed node handler, erased to operate on `dyn Any`.
+type Handler = Box<dyn Fn(&mut dyn Any)>;
+
+/// A set of type-keyed node handlers, run together in one traversal.
+#[derive(Default)]
+pub struct Pass {
+    handlers: HashMap<TypeId, Handler>,
+}
+
+impl Pass {
+    pub fn new() -> Self {
+        Pass::default()
+    }
+
+    /// Registers a handler that's invoked for every node of type `T`
+    /// encountered during the traversal, in pre-order (a container is
+    /// visited before its children).
+    pub fn on<T: 'static>(mut self, handler: impl Fn(&mut T) + 'static) -> Self {
+        self.handlers.insert(
+            TypeId::of::<T>(),
+            Box::new(move |any| {
+                if let Some(value) = any.downcast_mut::<T>() {
+                    handler(value);
+                }
+            }),
+        );
+        self
+    }
+
+    /// Runs every registered handler over `module` in a single
+    /// [`Visit::visit_mut`] traversal.
+    pub fn run(&self, module: &mut Module) {
+        let result: Result<(), crate::visit::VisitError<std::convert::Infallible>> =
+            module.visit_mut(&mut |node| {
+                if let Some(handler) = self.handlers.get(&(*node).type_id()) {
+                    handler(node);
+                }
+                Ok(())
+            });
+        result.expect("no lazy decoding happens during a pass over an already-decoded module");
+    }
+}
+
+/// Drops every custom section (including the name section), the way a
+/// minifying pass over a production build typically would.
+pub fn strip_custom_sections() -> Pass {
+    Pass::new().on::<Module>(|module| module.custom_sections.clear())
+}
+
+/// Rewrites every function index reference — `call` instructions and
+/// function exports — through `remap`, leaving indices it doesn't mention
+/// untouched. Useful after reordering or removing functions.
+pub fn remap_func_indices(remap: HashMap<u32, u32>) -> Pass {
+    let for_export = remap.clone();
+    Pass::new()
+        .on::<Export>(move |export| {
+            if let Some(&new_index) = for_export.get(&export.func_index) {
+                export.func_index = new_index;
+            }
+        })
+        .on::<Instruction>(move |instruction| {
+            if let Instruction::Call(index) = instruction {
+                if let Some(&new_index) = remap.get(index) {
+                    *index = new_index;
+                }
+            }
+        })
+}
+
+/// Folds `i32.const`/`i32.const`/arithmetic-op triples into a single
+/// `i32.const` carrying the computed result, repeating until no more
+/// folding opportunities remain in a function body.
+pub fn constant_fold() -> Pass {
+    Pass::new().on::<FuncBody>(|body| fold_constants(&mut body.instructions))
+}
+
+fn fold_constants(instructions: &mut Vec<Instruction>) {
+    // Recurse into nested blocks first so e.g. a `block`'s body is folded
+    // too, even though `Pass::run`'s own traversal will also reach it
+    // in pre-order (folding here is required; the outer traversal walking
+    // into it again is harmless, since a fully-folded body is a no-op).
+    for instruction in instructions.iter_mut() {
+        match instruction {
+            Instruction::Block(_, body) | Instruction::Loop(_, body) => fold_constants(body),
+            Instruction::If(_, then_body, else_body) => {
+                fold_constants(then_body);
+                fold_constants(else_body);
+            }
+            _ => {}
+        }
+    }
+
+    let mut changed = true;
+    while changed {
+        changed = false;
+        let mut i = 0;
+        while i + 3 <= instructions.len() {
+            if let [Instruction::I32Const(a), Instruction::I32Const(b), op] = &instructions[i..i + 3] {
+                let folded = match op {
+                    Instruction::I32Add => Some(a.wrapping_add(*b)),
+                    Instruction::I32Sub => Some(a.wrapping_sub(*b)),
+                    Instruction::I32Mul => Some(a.wrapping_mul(*b)),
+                    _ => None,
+                };
+                if let Some(result) = folded {
+                    instructions.splice(i..i + 3, [Instruction::I32Const(result)]);
+                    changed = true;
+                    continue;
+                }
+            }
+            i += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::CustomSection;
+
+    #[test]
+    fn strips_custom_sections() {
+        let mut module = Module::default();
+        module.custom_sections.push(CustomSection {
+            name: "name".to_owned(),
+            payload: vec![1, 2, 3],
+        });
+        strip_custom_sections().run(&mut module);
+        assert!(module.custom_sections.is_empty());
+    }
+
+    #[test]
+    fn remaps_call_and_export_indices() {
+        let mut module = Module::default();
+        module.exports.push(Export { name: "f".to_owned(), func_index: 0 });
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![Instruction::Call(0)],
+        });
+        let mut remap = HashMap::new();
+        remap.insert(0, 5);
+        remap_func_indices(remap).run(&mut module);
+        assert_eq!(module.exports[0].func_index, 5);
+        assert_eq!(module.code[0].instructions[0], Instruction::Call(5));
+    }
+
+    #[test]
+    fn folds_constant_arithmetic() {
+        let mut module = Module::default();
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![
+                Instruction::I32Const(2),
+                Instruction::I32Const(3),
+                Instruction::I32Add,
+                Instruction::I32Const(4),
+                Instruction::I32Mul,
+            ],
+        });
+        constant_fold().run(&mut module);
+        assert_eq!(module.code[0].instructions, vec![Instruction::I32Const(20)]);
+    }
+}
@@ -0,0 +1,45 @@
+//! The value and function types used throughout the module tree.
+
+use crate::io::DecodeError;
+
+/// One of the four number types a local, global, or stack slot can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ValueType {
+    I32,
+    I64,
+    F32,
+    F64,
+}
+
+impl ValueType {
+    pub(crate) fn from_byte(byte: u8) -> Result<Self, DecodeError> {
+        Ok(match byte {
+            0x7F => ValueType::I32,
+            0x7E => ValueType::I64,
+            0x7D => ValueType::F32,
+            0x7C => ValueType::F64,
+            other => {
+                return Err(DecodeError::UnexpectedByte {
+                    actual: other,
+                    expected: &[0x7F, 0x7E, 0x7D, 0x7C],
+                })
+            }
+        })
+    }
+
+    pub(crate) fn to_byte(self) -> u8 {
+        match self {
+            ValueType::I32 => 0x7F,
+            ValueType::I64 => 0x7E,
+            ValueType::F32 => 0x7D,
+            ValueType::F64 => 0x7C,
+        }
+    }
+}
+
+/// A function signature: parameter types followed by result types.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub struct FuncType {
+    pub params: Vec<ValueType>,
+    pub results: Vec<ValueType>,
+}
@@ -0,0 +1,687 @@
+//! The standard WebAssembly validation algorithm, run over a decoded
+//! [`Module`].
+//!
+//! Each function body is checked by walking its instructions while
+//! maintaining an abstract operand stack of [`ValueType`]s alongside a stack
+//! of control frames (one per `block`/`loop`/`if`/the implicit outermost
+//! function frame). A frame remembers its label's result types, the operand
+//! stack height at the point it was entered, and whether the frame has gone
+//! `unreachable` (after an unconditional branch, `return`, or
+//! `unreachable` itself) — once unreachable, any type may be popped, since
+//! the remaining code in that frame can never actually run.
+//!
+//! On top of per-function checking, [`Module::validate`] also runs the
+//! cross-section checks the binary format requires: the function and code
+//! sections must agree on length, and a data count section must be present
+//! before any function can reference a data segment by index.
+
+#[cfg(feature = "threads")]
+use crate::instructions::threads;
+use crate::instructions::{BlockType, Instruction};
+use crate::module::{FuncBody, Module};
+use crate::types::ValueType;
+use thiserror::Error;
+
+/// The maximum number of locals (including parameters) a single function
+/// may declare, per the WebAssembly core specification.
+const MAX_LOCALS: usize = 50_000;
+
+/// Everything [`Module::validate`] can reject a module for.
+#[derive(Debug, Error, PartialEq)]
+pub enum ValidationError {
+    #[error("function section has {funcs} entries but code section has {code}")]
+    FuncCodeLengthMismatch { funcs: usize, code: usize },
+
+    #[error("function {func_index} declares {count} locals, over the limit of {limit}")]
+    TooManyLocals {
+        func_index: u32,
+        count: usize,
+        limit: usize,
+    },
+
+    #[error("function {func_index} references type index {type_index}, which doesn't exist")]
+    TypeIndexOutOfRange { func_index: u32, type_index: u32 },
+
+    /// Also returned for a `local.get`/`local.set`/`local.tee` whose index
+    /// is past the function's parameters and locals.
+    #[error("function {func_index} at instruction {offset}: operand stack underflow")]
+    StackUnderflow { func_index: u32, offset: usize },
+
+    #[error("function {func_index} at instruction {offset}: expected {expected:?} on the stack, found {actual:?}")]
+    TypeMismatch {
+        func_index: u32,
+        offset: usize,
+        expected: ValueType,
+        actual: ValueType,
+    },
+
+    #[error("function {func_index} at instruction {offset}: branch to a non-existent label {depth}")]
+    InvalidLabel {
+        func_index: u32,
+        offset: usize,
+        depth: u32,
+    },
+
+    #[error("function {func_index}: control stack still has {depth} open blocks at the end of the body")]
+    UnclosedControlFrame { func_index: u32, depth: usize },
+
+    #[cfg(feature = "bulk-memory-operations")]
+    #[error("function {func_index} uses a bulk-memory instruction referencing a data segment, but no data count section is present")]
+    DataCountSectionRequired { func_index: u32 },
+}
+
+/// Walks `instructions` (recursing into nested blocks) looking for any
+/// bulk-memory instruction that references a data segment by index.
+#[cfg(feature = "bulk-memory-operations")]
+fn uses_data_segment(instructions: &[Instruction]) -> bool {
+    instructions.iter().any(|instruction| match instruction {
+        Instruction::BulkMemory(_) => true,
+        Instruction::Block(_, body) | Instruction::Loop(_, body) => uses_data_segment(body),
+        Instruction::If(_, then_body, else_body) => {
+            uses_data_segment(then_body) || uses_data_segment(else_body)
+        }
+        _ => false,
+    })
+}
+
+impl Module {
+    /// Runs the full validation algorithm over every function body, plus
+    /// the module-wide section consistency checks. Returns the first
+    /// problem found.
+    pub fn validate(&self) -> Result<(), ValidationError> {
+        if self.funcs.len() != self.code.len() {
+            return Err(ValidationError::FuncCodeLengthMismatch {
+                funcs: self.funcs.len(),
+                code: self.code.len(),
+            });
+        }
+
+        for (index, (&type_index, body)) in self.funcs.iter().zip(&self.code).enumerate() {
+            let func_index = index as u32;
+            let func_type = self
+                .types
+                .get(type_index as usize)
+                .ok_or(ValidationError::TypeIndexOutOfRange { func_index, type_index })?;
+
+            let local_count = func_type.params.len() + body.locals.len();
+            if local_count > MAX_LOCALS {
+                return Err(ValidationError::TooManyLocals {
+                    func_index,
+                    count: local_count,
+                    limit: MAX_LOCALS,
+                });
+            }
+
+            #[cfg(feature = "bulk-memory-operations")]
+            if self.data_count.is_none() && uses_data_segment(&body.instructions) {
+                return Err(ValidationError::DataCountSectionRequired { func_index });
+            }
+
+            let mut locals = func_type.params.clone();
+            locals.extend(body.locals.iter().copied());
+
+            FuncValidator {
+                func_index,
+                locals: &locals,
+                results: &func_type.results,
+            }
+            .validate_body(body)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single open `block`/`loop`/`if`/function-level control frame.
+struct Frame {
+    /// The value types a branch targeting this frame's label carries.
+    label_types: Vec<ValueType>,
+    /// The operand stack height when this frame was entered; branches and
+    /// `end` truncate back down to this (plus the label's results).
+    height: usize,
+    /// Set once this frame can no longer be reached by fallthrough (after
+    /// `unreachable`, `br`, `br_table`, or `return`), relaxing further pops.
+    unreachable: bool,
+}
+
+struct FuncValidator<'a> {
+    func_index: u32,
+    locals: &'a [ValueType],
+    results: &'a [ValueType],
+}
+
+impl FuncValidator<'_> {
+    fn validate_body(&self, body: &FuncBody) -> Result<(), ValidationError> {
+        let mut stack = Vec::new();
+        let mut frames = vec![Frame {
+            label_types: self.results.to_vec(),
+            height: 0,
+            unreachable: false,
+        }];
+        self.validate_instructions(&body.instructions, &mut stack, &mut frames, &mut 0)?;
+        if frames.len() != 1 {
+            return Err(ValidationError::UnclosedControlFrame {
+                func_index: self.func_index,
+                depth: frames.len() - 1,
+            });
+        }
+        self.pop_vals(&mut stack, &frames, self.results, &mut 0)?;
+        Ok(())
+    }
+
+    fn push_val(&self, stack: &mut Vec<ValueType>, ty: ValueType) {
+        stack.push(ty);
+    }
+
+    fn push_vals(&self, stack: &mut Vec<ValueType>, types: &[ValueType]) {
+        stack.extend_from_slice(types);
+    }
+
+    fn pop_val(
+        &self,
+        stack: &mut Vec<ValueType>,
+        frames: &[Frame],
+        expected: Option<ValueType>,
+        offset: &mut usize,
+    ) -> Result<ValueType, ValidationError> {
+        let frame = frames.last().unwrap();
+        if stack.len() == frame.height {
+            if frame.unreachable {
+                return Ok(expected.unwrap_or(ValueType::I32));
+            }
+            return Err(ValidationError::StackUnderflow {
+                func_index: self.func_index,
+                offset: *offset,
+            });
+        }
+        let actual = stack.pop().unwrap();
+        if let Some(expected) = expected {
+            if actual != expected {
+                return Err(ValidationError::TypeMismatch {
+                    func_index: self.func_index,
+                    offset: *offset,
+                    expected,
+                    actual,
+                });
+            }
+        }
+        Ok(actual)
+    }
+
+    fn pop_vals(
+        &self,
+        stack: &mut Vec<ValueType>,
+        frames: &[Frame],
+        expected: &[ValueType],
+        offset: &mut usize,
+    ) -> Result<(), ValidationError> {
+        for &ty in expected.iter().rev() {
+            self.pop_val(stack, frames, Some(ty), offset)?;
+        }
+        Ok(())
+    }
+
+    fn mark_unreachable(&self, stack: &mut Vec<ValueType>, frames: &mut [Frame]) {
+        let frame = frames.last_mut().unwrap();
+        stack.truncate(frame.height);
+        frame.unreachable = true;
+    }
+
+    fn validate_instructions(
+        &self,
+        instructions: &[Instruction],
+        stack: &mut Vec<ValueType>,
+        frames: &mut Vec<Frame>,
+        offset: &mut usize,
+    ) -> Result<(), ValidationError> {
+        for instruction in instructions {
+            self.validate_one(instruction, stack, frames, offset)?;
+            *offset += 1;
+        }
+        Ok(())
+    }
+
+    fn block_label_types(&self, ty: BlockType, is_loop: bool) -> Vec<ValueType> {
+        if is_loop {
+            // A `loop`'s label targets its start, so branching to it carries
+            // no values regardless of its result type.
+            Vec::new()
+        } else {
+            match ty {
+                BlockType::Empty => Vec::new(),
+                BlockType::Value(ty) => vec![ty],
+            }
+        }
+    }
+
+    fn block_result_types(&self, ty: BlockType) -> Vec<ValueType> {
+        match ty {
+            BlockType::Empty => Vec::new(),
+            BlockType::Value(ty) => vec![ty],
+        }
+    }
+
+    fn validate_one(
+        &self,
+        instruction: &Instruction,
+        stack: &mut Vec<ValueType>,
+        frames: &mut Vec<Frame>,
+        offset: &mut usize,
+    ) -> Result<(), ValidationError> {
+        use Instruction::*;
+        match instruction {
+            Unreachable => self.mark_unreachable(stack, frames),
+            Nop => {}
+            Block(ty, body) | Loop(ty, body) => {
+                let is_loop = matches!(instruction, Loop(..));
+                let label_types = self.block_label_types(*ty, is_loop);
+                frames.push(Frame {
+                    label_types,
+                    height: stack.len(),
+                    unreachable: false,
+                });
+                self.validate_instructions(body, stack, frames, offset)?;
+                let results = self.block_result_types(*ty);
+                self.pop_vals(stack, frames, &results, offset)?;
+                let frame = frames.pop().unwrap();
+                stack.truncate(frame.height);
+                self.push_vals(stack, &results);
+            }
+            If(ty, then_body, else_body) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                let results = self.block_result_types(*ty);
+                frames.push(Frame {
+                    label_types: results.clone(),
+                    height: stack.len(),
+                    unreachable: false,
+                });
+                self.validate_instructions(then_body, stack, frames, offset)?;
+                self.pop_vals(stack, frames, &results, offset)?;
+                let frame = frames.pop().unwrap();
+                stack.truncate(frame.height);
+                frames.push(Frame {
+                    label_types: results.clone(),
+                    height: frame.height,
+                    unreachable: false,
+                });
+                self.validate_instructions(else_body, stack, frames, offset)?;
+                self.pop_vals(stack, frames, &results, offset)?;
+                let frame = frames.pop().unwrap();
+                stack.truncate(frame.height);
+                self.push_vals(stack, &results);
+            }
+            Br(depth) => {
+                let label_types = self.label_types_at(frames, *depth, offset)?;
+                self.pop_vals(stack, frames, &label_types, offset)?;
+                self.mark_unreachable(stack, frames);
+            }
+            BrIf(depth) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                let label_types = self.label_types_at(frames, *depth, offset)?;
+                self.pop_vals(stack, frames, &label_types, offset)?;
+                self.push_vals(stack, &label_types);
+            }
+            BrTable { targets, default } => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                let label_types = self.label_types_at(frames, *default, offset)?;
+                for &target in targets {
+                    let other = self.label_types_at(frames, target, offset)?;
+                    if other != label_types {
+                        return Err(ValidationError::InvalidLabel {
+                            func_index: self.func_index,
+                            offset: *offset,
+                            depth: target,
+                        });
+                    }
+                }
+                self.pop_vals(stack, frames, &label_types, offset)?;
+                self.mark_unreachable(stack, frames);
+            }
+            Return => {
+                let results = self.results.to_vec();
+                self.pop_vals(stack, frames, &results, offset)?;
+                self.mark_unreachable(stack, frames);
+            }
+            Drop => {
+                self.pop_val(stack, frames, None, offset)?;
+            }
+            Select => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                let a = self.pop_val(stack, frames, None, offset)?;
+                self.pop_val(stack, frames, Some(a), offset)?;
+                self.push_val(stack, a);
+            }
+            LocalGet(index) => {
+                let ty = *self.locals.get(*index as usize).ok_or(ValidationError::StackUnderflow {
+                    func_index: self.func_index,
+                    offset: *offset,
+                })?;
+                self.push_val(stack, ty);
+            }
+            LocalSet(index) | LocalTee(index) => {
+                let ty = *self.locals.get(*index as usize).ok_or(ValidationError::StackUnderflow {
+                    func_index: self.func_index,
+                    offset: *offset,
+                })?;
+                self.pop_val(stack, frames, Some(ty), offset)?;
+                if matches!(instruction, LocalTee(_)) {
+                    self.push_val(stack, ty);
+                }
+            }
+            // Globals aren't modeled in this Module yet, so their type
+            // can't be checked; treat them as opaque i32 for now.
+            GlobalGet(_) => self.push_val(stack, ValueType::I32),
+            GlobalSet(_) => {
+                self.pop_val(stack, frames, None, offset)?;
+            }
+            Call(_) | CallIndirect { .. } => {
+                // Without a resolved call target's signature on hand, we
+                // can't check arity here; callers are still checked for
+                // internal consistency by every other rule.
+            }
+            I32Load(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            I64Load(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I64);
+            }
+            F32Load(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::F32);
+            }
+            F64Load(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::F64);
+            }
+            I32Store(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+            }
+            I64Store(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I64), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+            }
+            F32Store(_) => {
+                self.pop_val(stack, frames, Some(ValueType::F32), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+            }
+            F64Store(_) => {
+                self.pop_val(stack, frames, Some(ValueType::F64), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+            }
+            MemorySize => self.push_val(stack, ValueType::I32),
+            MemoryGrow => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            I32Add | I32Sub | I32Mul => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            I32Const(_) => self.push_val(stack, ValueType::I32),
+            I64Const(_) => self.push_val(stack, ValueType::I64),
+            F32Const(_) => self.push_val(stack, ValueType::F32),
+            F64Const(_) => self.push_val(stack, ValueType::F64),
+            #[cfg(feature = "threads")]
+            Atomic(atomic) => self.validate_atomic(atomic, stack, frames, offset)?,
+            // Presence of the data count section (required whenever these
+            // appear) is checked module-wide in `Module::validate`.
+            // `memory.init` pops its destination, source offset and length;
+            // `data.drop` has no operand-stack effect.
+            #[cfg(feature = "bulk-memory-operations")]
+            BulkMemory(bulk_memory) => {
+                if let crate::instructions::bulk_memory::BulkMemoryInstruction::MemoryInit { .. } = bulk_memory {
+                    for _ in 0..3 {
+                        self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "threads")]
+    fn validate_atomic(
+        &self,
+        atomic: &threads::AtomicInstruction,
+        stack: &mut Vec<ValueType>,
+        frames: &[Frame],
+        offset: &mut usize,
+    ) -> Result<(), ValidationError> {
+        use threads::{AtomicInstruction, Width};
+
+        let width_ty = |width: Width| match width {
+            Width::I32 => ValueType::I32,
+            Width::I64 => ValueType::I64,
+        };
+
+        match *atomic {
+            AtomicInstruction::Notify(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            AtomicInstruction::Wait32(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I64), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            AtomicInstruction::Wait64(_) => {
+                self.pop_val(stack, frames, Some(ValueType::I64), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I64), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ValueType::I32);
+            }
+            AtomicInstruction::Fence => {}
+            AtomicInstruction::Load { width, .. } => {
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, width_ty(width));
+            }
+            AtomicInstruction::Store { width, .. } => {
+                self.pop_val(stack, frames, Some(width_ty(width)), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+            }
+            AtomicInstruction::Rmw { op, width, .. } => {
+                let ty = width_ty(width);
+                if matches!(op, threads::RmwOp::Cmpxchg) {
+                    self.pop_val(stack, frames, Some(ty), offset)?;
+                }
+                self.pop_val(stack, frames, Some(ty), offset)?;
+                self.pop_val(stack, frames, Some(ValueType::I32), offset)?;
+                self.push_val(stack, ty);
+            }
+        }
+        Ok(())
+    }
+
+    fn label_types_at(&self, frames: &[Frame], depth: u32, offset: &mut usize) -> Result<Vec<ValueType>, ValidationError> {
+        let index = frames
+            .len()
+            .checked_sub(1)
+            .and_then(|top| top.checked_sub(depth as usize));
+        match index {
+            Some(index) => Ok(frames[index].label_types.clone()),
+            None => Err(ValidationError::InvalidLabel {
+                func_index: self.func_index,
+                offset: *offset,
+                depth,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::FuncType;
+
+    fn func(params: Vec<ValueType>, results: Vec<ValueType>, instructions: Vec<Instruction>) -> Module {
+        let mut module = Module::default();
+        module.types.push(FuncType { params, results });
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions,
+        });
+        module
+    }
+
+    #[test]
+    fn accepts_a_well_typed_function() {
+        let module = func(
+            vec![ValueType::I32, ValueType::I32],
+            vec![ValueType::I32],
+            vec![Instruction::LocalGet(0), Instruction::LocalGet(1), Instruction::I32Add],
+        );
+        assert_eq!(module.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_stack_underflow() {
+        // `local.get 0` in a function with no params or locals: the
+        // out-of-range index is reported as `StackUnderflow`.
+        let module = func(vec![], vec![ValueType::I32], vec![Instruction::LocalGet(0)]);
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::StackUnderflow { func_index: 0, offset: 0 })
+        );
+    }
+
+    #[test]
+    fn rejects_a_function_code_length_mismatch() {
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::FuncCodeLengthMismatch { funcs: 1, code: 0 })
+        );
+    }
+
+    #[test]
+    fn rejects_a_type_mismatch() {
+        let module = func(
+            vec![ValueType::I32],
+            vec![ValueType::F32],
+            vec![Instruction::LocalGet(0)],
+        );
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::TypeMismatch {
+                func_index: 0,
+                offset: 0,
+                expected: ValueType::F32,
+                actual: ValueType::I32,
+            })
+        );
+    }
+
+    #[test]
+    fn rejects_a_branch_to_a_non_existent_label() {
+        // `br 0` targets the function's own (implicit) frame, which is
+        // valid; there's no frame at depth 1 with only that one frame open.
+        let module = func(vec![], vec![], vec![Instruction::Br(1)]);
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::InvalidLabel { func_index: 0, offset: 0, depth: 1 })
+        );
+    }
+
+    #[test]
+    fn rejects_too_many_locals() {
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: vec![ValueType::I32; MAX_LOCALS + 1],
+            instructions: Vec::new(),
+        });
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::TooManyLocals {
+                func_index: 0,
+                count: MAX_LOCALS + 1,
+                limit: MAX_LOCALS,
+            })
+        );
+    }
+
+    #[test]
+    fn accepts_nested_control_flow_that_balances_the_stack() {
+        // (block (result i32) (loop (i32.const 0) (br 1)) (i32.const 2))
+        let module = func(
+            vec![],
+            vec![ValueType::I32],
+            vec![Instruction::Block(
+                BlockType::Value(ValueType::I32),
+                vec![
+                    Instruction::Loop(
+                        BlockType::Empty,
+                        vec![Instruction::I32Const(0), Instruction::Br(1)],
+                    ),
+                    Instruction::I32Const(2),
+                ],
+            )],
+        );
+        assert_eq!(module.validate(), Ok(()));
+    }
+
+    #[test]
+    fn rejects_a_branch_out_of_a_nested_block_with_the_wrong_type() {
+        // (block (result i32) (block (result f32) (f32.const 0) (br 1)))
+        let module = func(
+            vec![],
+            vec![ValueType::I32],
+            vec![Instruction::Block(
+                BlockType::Value(ValueType::I32),
+                vec![Instruction::Block(
+                    BlockType::Value(ValueType::F32),
+                    vec![Instruction::F32Const(0.0), Instruction::Br(1)],
+                )],
+            )],
+        );
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::TypeMismatch {
+                func_index: 0,
+                offset: 1,
+                expected: ValueType::I32,
+                actual: ValueType::F32,
+            })
+        );
+    }
+
+    #[cfg(feature = "bulk-memory-operations")]
+    #[test]
+    fn requires_a_data_count_section_for_bulk_memory_instructions() {
+        use crate::instructions::bulk_memory::BulkMemoryInstruction;
+
+        let module = func(
+            vec![],
+            vec![],
+            vec![Instruction::BulkMemory(BulkMemoryInstruction::DataDrop { data_index: 0 })],
+        );
+        assert_eq!(
+            module.validate(),
+            Err(ValidationError::DataCountSectionRequired { func_index: 0 })
+        );
+    }
+
+    #[cfg(feature = "bulk-memory-operations")]
+    #[test]
+    fn accepts_bulk_memory_instructions_when_a_data_count_section_is_present() {
+        use crate::instructions::bulk_memory::BulkMemoryInstruction;
+
+        let mut module = func(
+            vec![],
+            vec![],
+            vec![Instruction::BulkMemory(BulkMemoryInstruction::DataDrop { data_index: 0 })],
+        );
+        module.data_count = Some(1);
+        assert_eq!(module.validate(), Ok(()));
+    }
+}
@@ -0,0 +1,102 @@
+//! Generic pre-order traversal over a decoded module tree.
+//!
+//! [`Visit::visit_mut`] walks every node of a subtree — containers before
+//! their children — invoking a callback on each one with the node erased to
+//! `&mut dyn Any`. [`crate::pass::Pass`] builds directly on this: it's a
+//! thin, type-keyed dispatch table on top of the one traversal every node
+//! already knows how to do, rather than a second, separately-written walk
+//! over the tree.
+//!
+//! Other implementations of wasmbin decode lazily, keeping large subtrees
+//! (function bodies, custom section payloads) around as raw bytes until
+//! something asks to look inside them; on a lazy backend, `visit_mut` is
+//! also the mechanism that would force that decoding as it walks past a
+//! not-yet-decoded node. This crate currently decodes everything eagerly
+//! (see [`crate::module`]), so there's nothing to force yet — but the
+//! traversal itself, and every pass built on it, already aren't coupled to
+//! that eagerness, so plugging in lazy decoding later is a change to
+//! `Module`'s decode path, not to this trait or to `Pass`.
+
+use crate::instructions::Instruction;
+use crate::io::DecodeError;
+use crate::module::{CustomSection, Export, FuncBody, Module};
+use std::any::Any;
+
+/// Anything that can go wrong while visiting a tree, beyond a caller's own
+/// callback failing.
+#[derive(Debug)]
+pub enum VisitError<E = std::convert::Infallible> {
+    /// Forcing a lazily-decoded subtree failed.
+    LazyDecode(DecodeError),
+    /// The visiting callback itself returned an error.
+    Custom(E),
+}
+
+impl<E> From<DecodeError> for VisitError<E> {
+    fn from(err: DecodeError) -> Self {
+        VisitError::LazyDecode(err)
+    }
+}
+
+/// A node in the module tree that can be recursively visited.
+pub trait Visit: Sized {
+    /// Visits this node, then recurses into its children, pre-order.
+    /// `f` is called once per node with the node erased to `&mut dyn Any`,
+    /// so a caller can key behavior off the node's concrete type (see
+    /// [`crate::pass::Pass::on`]) without this trait needing to know about
+    /// every node type a caller might care about.
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>>;
+}
+
+impl<T: Visit> Visit for Vec<T> {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        for item in self.iter_mut() {
+            item.visit_mut(f)?;
+        }
+        Ok(())
+    }
+}
+
+impl Visit for Module {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        f(self).map_err(VisitError::Custom)?;
+        self.custom_sections.visit_mut(f)?;
+        self.exports.visit_mut(f)?;
+        self.code.visit_mut(f)?;
+        Ok(())
+    }
+}
+
+impl Visit for CustomSection {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        f(self).map_err(VisitError::Custom)
+    }
+}
+
+impl Visit for Export {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        f(self).map_err(VisitError::Custom)
+    }
+}
+
+impl Visit for FuncBody {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        f(self).map_err(VisitError::Custom)?;
+        self.instructions.visit_mut(f)
+    }
+}
+
+impl Visit for Instruction {
+    fn visit_mut<E>(&mut self, f: &mut dyn FnMut(&mut dyn Any) -> Result<(), E>) -> Result<(), VisitError<E>> {
+        f(self).map_err(VisitError::Custom)?;
+        match self {
+            Instruction::Block(_, body) | Instruction::Loop(_, body) => body.visit_mut(f)?,
+            Instruction::If(_, then_body, else_body) => {
+                then_body.visit_mut(f)?;
+                else_body.visit_mut(f)?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,1081 @@
+//! Textual WebAssembly (WAT) printing and parsing for [`Module`], so a round
+//! trip through the text format doesn't require pulling in an external
+//! toolchain.
+//!
+//! Coverage is intentionally scoped to what [`Module`] models as structured
+//! data: `type`, `func` (including its body) and `export` entries, plus
+//! numeric literals in decimal, hex-float, and NaN-payload form. Import,
+//! memory, table, global, data, and element sections aren't surfaced here —
+//! `Module` only keeps those as the opaque
+//! [`RawSection`](crate::module::RawSection) bytes needed to round-trip the
+//! binary format, not as anything with a sensible text-format shape. Growing
+//! this module to cover them is gated on `Module` modeling those sections
+//! first; until then, `to_wat`/`from_wat` are still useful for diffing and
+//! hand-editing the functions and exports a module has.
+//!
+//! Output is the flat (non-folded) text form: one instruction per line,
+//! `block`/`loop`/`if` indented rather than written as nested
+//! S-expressions. That's what `from_wat` expects back, too.
+
+use crate::instructions::{BlockType, Instruction};
+use crate::module::{Export, FuncBody, Module};
+use crate::types::{FuncType, ValueType};
+use std::fmt::Write as _;
+
+/// Something went wrong turning WAT text back into a [`Module`].
+#[derive(Debug, PartialEq)]
+pub struct WatParseError {
+    pub message: String,
+}
+
+impl std::fmt::Display for WatParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for WatParseError {}
+
+fn err(message: impl Into<String>) -> WatParseError {
+    WatParseError { message: message.into() }
+}
+
+impl ValueType {
+    fn mnemonic(self) -> &'static str {
+        match self {
+            ValueType::I32 => "i32",
+            ValueType::I64 => "i64",
+            ValueType::F32 => "f32",
+            ValueType::F64 => "f64",
+        }
+    }
+
+    fn from_mnemonic(s: &str) -> Result<Self, WatParseError> {
+        Ok(match s {
+            "i32" => ValueType::I32,
+            "i64" => ValueType::I64,
+            "f32" => ValueType::F32,
+            "f64" => ValueType::F64,
+            other => return Err(err(format!("unknown value type '{other}'"))),
+        })
+    }
+}
+
+impl Module {
+    /// Renders this module as canonical WebAssembly text format.
+    pub fn to_wat(&self) -> String {
+        let mut out = String::new();
+        out.push_str("(module\n");
+        for ty in &self.types {
+            writeln!(out, "  (type {})", format_func_type(ty)).unwrap();
+        }
+        for (&type_index, body) in self.funcs.iter().zip(&self.code) {
+            write_func(&mut out, type_index, self.types.get(type_index as usize), body);
+        }
+        for export in &self.exports {
+            writeln!(out, "  (export \"{}\" (func {}))", escape_string(&export.name), export.func_index).unwrap();
+        }
+        out.push_str(")\n");
+        out
+    }
+
+    /// Parses WebAssembly text format back into a [`Module`].
+    pub fn from_wat(text: &str) -> Result<Self, WatParseError> {
+        let tokens = tokenize(text)?;
+        let mut parser = Parser { tokens: &tokens, pos: 0 };
+        parser.expect_open()?;
+        parser.expect_atom("module")?;
+
+        let mut module = Module::default();
+        while !parser.peek_close() {
+            parser.expect_open()?;
+            let keyword = parser.next_atom()?;
+            match keyword.as_str() {
+                "type" => {
+                    module.types.push(parse_func_type(&mut parser)?);
+                }
+                "func" => {
+                    let (type_index, body) = parse_func(&mut parser, &module.types)?;
+                    module.funcs.push(type_index);
+                    module.code.push(body);
+                }
+                "export" => {
+                    let name = parser.next_string()?;
+                    parser.expect_open()?;
+                    parser.expect_atom("func")?;
+                    let func_index = parser.next_u32()?;
+                    parser.expect_close()?;
+                    module.exports.push(Export { name, func_index });
+                }
+                other => return Err(err(format!("unsupported module field '{other}'"))),
+            }
+            parser.expect_close()?;
+        }
+        parser.expect_close()?;
+        if parser.pos != parser.tokens.len() {
+            return Err(err("trailing tokens after module"));
+        }
+        Ok(module)
+    }
+}
+
+fn format_func_type(ty: &FuncType) -> String {
+    let mut out = String::from("(func");
+    for param in &ty.params {
+        write!(out, " (param {})", param.mnemonic()).unwrap();
+    }
+    for result in &ty.results {
+        write!(out, " (result {})", result.mnemonic()).unwrap();
+    }
+    out.push(')');
+    out
+}
+
+fn parse_func_type(parser: &mut Parser) -> Result<FuncType, WatParseError> {
+    parser.expect_open()?;
+    parser.expect_atom("func")?;
+    let mut ty = FuncType::default();
+    while parser.peek_open() {
+        parser.expect_open()?;
+        match parser.next_atom()?.as_str() {
+            "param" => {
+                while !parser.peek_close() {
+                    ty.params.push(ValueType::from_mnemonic(&parser.next_atom()?)?);
+                }
+            }
+            "result" => {
+                while !parser.peek_close() {
+                    ty.results.push(ValueType::from_mnemonic(&parser.next_atom()?)?);
+                }
+            }
+            other => return Err(err(format!("unsupported type field '{other}'"))),
+        }
+        parser.expect_close()?;
+    }
+    parser.expect_close()?;
+    Ok(ty)
+}
+
+fn write_func(out: &mut String, type_index: u32, ty: Option<&FuncType>, body: &FuncBody) {
+    writeln!(out, "  (func (type {type_index})").unwrap();
+    if let Some(ty) = ty {
+        for param in &ty.params {
+            writeln!(out, "    (param {})", param.mnemonic()).unwrap();
+        }
+        for result in &ty.results {
+            writeln!(out, "    (result {})", result.mnemonic()).unwrap();
+        }
+    }
+    if !body.locals.is_empty() {
+        let mnemonics: Vec<_> = body.locals.iter().map(|ty| ty.mnemonic()).collect();
+        writeln!(out, "    (local {})", mnemonics.join(" ")).unwrap();
+    }
+    write_instructions(out, &body.instructions, 2);
+    out.push_str("  )\n");
+}
+
+fn write_instructions(out: &mut String, instructions: &[Instruction], indent: usize) {
+    let pad = "  ".repeat(indent);
+    for instruction in instructions {
+        match instruction {
+            Instruction::Block(ty, instrs) => {
+                writeln!(out, "{pad}block{}", block_type_suffix(*ty)).unwrap();
+                write_instructions(out, instrs, indent + 1);
+                writeln!(out, "{pad}end").unwrap();
+            }
+            Instruction::Loop(ty, instrs) => {
+                writeln!(out, "{pad}loop{}", block_type_suffix(*ty)).unwrap();
+                write_instructions(out, instrs, indent + 1);
+                writeln!(out, "{pad}end").unwrap();
+            }
+            Instruction::If(ty, then_body, else_body) => {
+                writeln!(out, "{pad}if{}", block_type_suffix(*ty)).unwrap();
+                write_instructions(out, then_body, indent + 1);
+                if !else_body.is_empty() {
+                    writeln!(out, "{pad}else").unwrap();
+                    write_instructions(out, else_body, indent + 1);
+                }
+                writeln!(out, "{pad}end").unwrap();
+            }
+            other => writeln!(out, "{pad}{}", format_plain_instruction(other)).unwrap(),
+        }
+    }
+}
+
+fn block_type_suffix(ty: BlockType) -> String {
+    match ty {
+        BlockType::Empty => String::new(),
+        BlockType::Value(ty) => format!(" (result {})", ty.mnemonic()),
+    }
+}
+
+fn format_plain_instruction(instruction: &Instruction) -> String {
+    use Instruction::*;
+    match instruction {
+        Unreachable => "unreachable".to_owned(),
+        Nop => "nop".to_owned(),
+        Br(label) => format!("br {label}"),
+        BrIf(label) => format!("br_if {label}"),
+        BrTable { targets, default } => {
+            let targets: Vec<_> = targets.iter().map(u32::to_string).collect();
+            format!("br_table {} {default}", targets.join(" "))
+        }
+        Return => "return".to_owned(),
+        Call(index) => format!("call {index}"),
+        CallIndirect { type_index, table_index } => format!("call_indirect (type {type_index}) (table {table_index})"),
+        Drop => "drop".to_owned(),
+        Select => "select".to_owned(),
+        LocalGet(i) => format!("local.get {i}"),
+        LocalSet(i) => format!("local.set {i}"),
+        LocalTee(i) => format!("local.tee {i}"),
+        GlobalGet(i) => format!("global.get {i}"),
+        GlobalSet(i) => format!("global.set {i}"),
+        I32Load(m) => format!("i32.load{}", mem_arg_suffix(m)),
+        I64Load(m) => format!("i64.load{}", mem_arg_suffix(m)),
+        F32Load(m) => format!("f32.load{}", mem_arg_suffix(m)),
+        F64Load(m) => format!("f64.load{}", mem_arg_suffix(m)),
+        I32Store(m) => format!("i32.store{}", mem_arg_suffix(m)),
+        I64Store(m) => format!("i64.store{}", mem_arg_suffix(m)),
+        F32Store(m) => format!("f32.store{}", mem_arg_suffix(m)),
+        F64Store(m) => format!("f64.store{}", mem_arg_suffix(m)),
+        MemorySize => "memory.size".to_owned(),
+        MemoryGrow => "memory.grow".to_owned(),
+        I32Const(v) => format!("i32.const {v}"),
+        I64Const(v) => format!("i64.const {v}"),
+        F32Const(v) => format!("f32.const {}", format_f32(*v)),
+        F64Const(v) => format!("f64.const {}", format_f64(*v)),
+        I32Add => "i32.add".to_owned(),
+        I32Sub => "i32.sub".to_owned(),
+        I32Mul => "i32.mul".to_owned(),
+        Block(..) | Loop(..) | If(..) => unreachable!("structured instructions handled separately"),
+        #[cfg(feature = "threads")]
+        Atomic(atomic) => format_atomic_instruction(atomic),
+        #[cfg(feature = "bulk-memory-operations")]
+        BulkMemory(bulk_memory) => format_bulk_memory_instruction(bulk_memory),
+    }
+}
+
+#[cfg(feature = "threads")]
+fn format_atomic_instruction(instruction: &crate::instructions::threads::AtomicInstruction) -> String {
+    use crate::instructions::threads::{AtomicInstruction, Width};
+
+    let width_mnemonic = |width: Width| match width {
+        Width::I32 => "i32",
+        Width::I64 => "i64",
+    };
+
+    match *instruction {
+        AtomicInstruction::Notify(arg) => format!("memory.atomic.notify{}", mem_arg_suffix(&arg)),
+        AtomicInstruction::Wait32(arg) => format!("memory.atomic.wait32{}", mem_arg_suffix(&arg)),
+        AtomicInstruction::Wait64(arg) => format!("memory.atomic.wait64{}", mem_arg_suffix(&arg)),
+        AtomicInstruction::Fence => "atomic.fence".to_owned(),
+        AtomicInstruction::Load { width, narrow, arg } => {
+            let narrow = narrow.map_or(String::new(), |n| format!("{}_u", narrow_bits(n)));
+            format!("{}.atomic.load{narrow}{}", width_mnemonic(width), mem_arg_suffix(&arg))
+        }
+        AtomicInstruction::Store { width, narrow, arg } => {
+            let narrow = narrow.map_or(String::new(), |n| narrow_bits(n).to_string());
+            format!("{}.atomic.store{narrow}{}", width_mnemonic(width), mem_arg_suffix(&arg))
+        }
+        AtomicInstruction::Rmw { op, width, narrow, arg } => {
+            let narrow_u = narrow.map_or(String::new(), |n| format!("{}", narrow_bits(n)));
+            let suffix = if narrow.is_some() { "_u" } else { "" };
+            format!(
+                "{}.atomic.rmw{narrow_u}.{}{suffix}{}",
+                width_mnemonic(width),
+                rmw_op_mnemonic(op),
+                mem_arg_suffix(&arg)
+            )
+        }
+    }
+}
+
+#[cfg(feature = "threads")]
+fn narrow_bits(narrow: crate::instructions::threads::Narrow) -> u32 {
+    use crate::instructions::threads::Narrow;
+    match narrow {
+        Narrow::W8 => 8,
+        Narrow::W16 => 16,
+        Narrow::W32 => 32,
+    }
+}
+
+#[cfg(feature = "threads")]
+fn rmw_op_mnemonic(op: crate::instructions::threads::RmwOp) -> &'static str {
+    use crate::instructions::threads::RmwOp;
+    match op {
+        RmwOp::Add => "add",
+        RmwOp::Sub => "sub",
+        RmwOp::And => "and",
+        RmwOp::Or => "or",
+        RmwOp::Xor => "xor",
+        RmwOp::Xchg => "xchg",
+        RmwOp::Cmpxchg => "cmpxchg",
+    }
+}
+
+#[cfg(feature = "bulk-memory-operations")]
+fn format_bulk_memory_instruction(instruction: &crate::instructions::bulk_memory::BulkMemoryInstruction) -> String {
+    use crate::instructions::bulk_memory::BulkMemoryInstruction;
+    match *instruction {
+        BulkMemoryInstruction::MemoryInit { data_index } => format!("memory.init {data_index}"),
+        BulkMemoryInstruction::DataDrop { data_index } => format!("data.drop {data_index}"),
+    }
+}
+
+fn mem_arg_suffix(m: &crate::io::MemArg) -> String {
+    let mut out = String::new();
+    if m.offset != 0 {
+        write!(out, " offset={}", m.offset).unwrap();
+    }
+    if m.align != 0 {
+        write!(out, " align={}", 1u32 << m.align).unwrap();
+    }
+    out
+}
+
+fn format_f32(v: f32) -> String {
+    format_float_bits(u64::from(v.to_bits()), 23, 8)
+}
+
+fn format_f64(v: f64) -> String {
+    format_float_bits(v.to_bits(), 52, 11)
+}
+
+/// Renders an IEEE-754 bit pattern in the WAT spec's float literal syntax:
+/// `inf`/`nan` for the special values, `nan:0x<payload>` for a NaN whose
+/// payload isn't the canonical quiet one, and hex-float notation
+/// (`0x1.8p+1`) otherwise — the only form that can represent every finite
+/// value exactly, unlike a decimal literal.
+fn format_float_bits(bits: u64, mantissa_bits: u32, exponent_bits: u32) -> String {
+    let bias = (1i64 << (exponent_bits - 1)) - 1;
+    let sign_negative = (bits >> (mantissa_bits + exponent_bits)) & 1 == 1;
+    let exponent_field = (bits >> mantissa_bits) & ((1u64 << exponent_bits) - 1);
+    let mantissa = bits & ((1u64 << mantissa_bits) - 1);
+    let sign = if sign_negative { "-" } else { "" };
+
+    let max_exponent_field = (1u64 << exponent_bits) - 1;
+    if exponent_field == max_exponent_field {
+        if mantissa == 0 {
+            return format!("{sign}inf");
+        }
+        let quiet_bit = 1u64 << (mantissa_bits - 1);
+        return if mantissa == quiet_bit {
+            format!("{sign}nan")
+        } else {
+            format!("{sign}nan:{mantissa:#x}")
+        };
+    }
+    if exponent_field == 0 && mantissa == 0 {
+        return format!("{sign}0x0p+0");
+    }
+
+    let (leading, exponent) = if exponent_field == 0 {
+        (0u64, 1 - bias)
+    } else {
+        (1u64, exponent_field as i64 - bias)
+    };
+
+    // Pad the mantissa out to a whole number of hex digits, then drop
+    // trailing zero digits so e.g. `1.5` prints as `0x1.8p+0`, not
+    // `0x1.800000p+0`.
+    let pad = (4 - mantissa_bits % 4) % 4;
+    let hex_digits = ((mantissa_bits + pad) / 4) as usize;
+    let mut hex = format!("{:0width$x}", mantissa << pad, width = hex_digits);
+    while hex.ends_with('0') && hex.len() > 1 {
+        hex.pop();
+    }
+    if hex == "0" {
+        format!("{sign}0x{leading}p{exponent:+}")
+    } else {
+        format!("{sign}0x{leading}.{hex}p{exponent:+}")
+    }
+}
+
+fn escape_string(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+// --- A small S-expression tokenizer/parser, just enough to round-trip
+// --- what `to_wat` above emits. ---
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Atom(String),
+    Str(String),
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>, WatParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            '(' => {
+                chars.next();
+                tokens.push(Token::Open);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::Close);
+            }
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            ';' => {
+                chars.next();
+                if chars.peek() == Some(&';') {
+                    for c in chars.by_ref() {
+                        if c == '\n' {
+                            break;
+                        }
+                    }
+                } else {
+                    return Err(err("unexpected ';'"));
+                }
+            }
+            '"' => {
+                chars.next();
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('"') => s.push('"'),
+                            Some('\\') => s.push('\\'),
+                            other => return Err(err(format!("bad string escape {other:?}"))),
+                        },
+                        Some(c) => s.push(c),
+                        None => return Err(err("unterminated string")),
+                    }
+                }
+                tokens.push(Token::Str(s));
+            }
+            _ => {
+                let mut atom = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    atom.push(c);
+                    chars.next();
+                }
+                tokens.push(Token::Atom(atom));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser<'a> {
+    tokens: &'a [Token],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn peek_open(&self) -> bool {
+        matches!(self.peek(), Some(Token::Open))
+    }
+
+    fn peek_close(&self) -> bool {
+        matches!(self.peek(), Some(Token::Close))
+    }
+
+    fn expect_open(&mut self) -> Result<(), WatParseError> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Open) => {
+                self.pos += 1;
+                Ok(())
+            }
+            other => Err(err(format!("expected '(', found {other:?}"))),
+        }
+    }
+
+    fn expect_close(&mut self) -> Result<(), WatParseError> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Close) => {
+                self.pos += 1;
+                Ok(())
+            }
+            other => Err(err(format!("expected ')', found {other:?}"))),
+        }
+    }
+
+    fn next_atom(&mut self) -> Result<String, WatParseError> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Atom(a)) => {
+                self.pos += 1;
+                Ok(a.clone())
+            }
+            other => Err(err(format!("expected an atom, found {other:?}"))),
+        }
+    }
+
+    fn expect_atom(&mut self, expected: &str) -> Result<(), WatParseError> {
+        let atom = self.next_atom()?;
+        if atom != expected {
+            return Err(err(format!("expected '{expected}', found '{atom}'")));
+        }
+        Ok(())
+    }
+
+    fn next_string(&mut self) -> Result<String, WatParseError> {
+        match self.tokens.get(self.pos) {
+            Some(Token::Str(s)) => {
+                self.pos += 1;
+                Ok(s.clone())
+            }
+            other => Err(err(format!("expected a string literal, found {other:?}"))),
+        }
+    }
+
+    fn next_u32(&mut self) -> Result<u32, WatParseError> {
+        self.next_atom()?.parse().map_err(|_| err("expected a u32"))
+    }
+}
+
+fn parse_func(parser: &mut Parser, types: &[FuncType]) -> Result<(u32, FuncBody), WatParseError> {
+    parser.expect_open()?;
+    parser.expect_atom("type")?;
+    let type_index = parser.next_u32()?;
+    parser.expect_close()?;
+
+    let func_type = types
+        .get(type_index as usize)
+        .ok_or_else(|| err(format!("function references unknown type {type_index}")))?;
+
+    // Skip the `(param ...)`/`(result ...)` annotations `to_wat` repeats for
+    // readability: they're redundant with the referenced type.
+    while parser.peek_open() {
+        let save = parser.pos;
+        parser.expect_open()?;
+        let keyword = parser.next_atom()?;
+        if keyword == "param" || keyword == "result" {
+            let mut depth = 1;
+            while depth > 0 {
+                match parser.tokens.get(parser.pos) {
+                    Some(Token::Open) => depth += 1,
+                    Some(Token::Close) => depth -= 1,
+                    Some(_) => {}
+                    None => return Err(err("unexpected end of input")),
+                }
+                parser.pos += 1;
+            }
+        } else {
+            parser.pos = save;
+            break;
+        }
+    }
+
+    let mut locals = Vec::new();
+    while parser.peek_open() {
+        let save = parser.pos;
+        parser.expect_open()?;
+        if parser.next_atom()? == "local" {
+            while !parser.peek_close() {
+                locals.push(ValueType::from_mnemonic(&parser.next_atom()?)?);
+            }
+            parser.expect_close()?;
+        } else {
+            parser.pos = save;
+            break;
+        }
+    }
+
+    let instructions = parse_instructions(parser)?;
+    let _ = func_type;
+    Ok((type_index, FuncBody { locals, instructions }))
+}
+
+fn parse_instructions(parser: &mut Parser) -> Result<Vec<Instruction>, WatParseError> {
+    let mut instructions = Vec::new();
+    loop {
+        let mnemonic = match parser.peek() {
+            Some(Token::Atom(a)) if a == "end" || a == "else" => break,
+            Some(Token::Atom(_)) => parser.next_atom()?,
+            _ => break,
+        };
+        instructions.push(parse_instruction(parser, &mnemonic)?);
+    }
+    Ok(instructions)
+}
+
+fn parse_block_type(parser: &mut Parser) -> Result<BlockType, WatParseError> {
+    if parser.peek_open() {
+        let save = parser.pos;
+        parser.expect_open()?;
+        if parser.next_atom()? == "result" {
+            let ty = ValueType::from_mnemonic(&parser.next_atom()?)?;
+            parser.expect_close()?;
+            return Ok(BlockType::Value(ty));
+        }
+        parser.pos = save;
+    }
+    Ok(BlockType::Empty)
+}
+
+fn parse_instruction(parser: &mut Parser, mnemonic: &str) -> Result<Instruction, WatParseError> {
+    Ok(match mnemonic {
+        "unreachable" => Instruction::Unreachable,
+        "nop" => Instruction::Nop,
+        "block" => {
+            let ty = parse_block_type(parser)?;
+            let body = parse_instructions(parser)?;
+            parser.expect_atom("end")?;
+            Instruction::Block(ty, body)
+        }
+        "loop" => {
+            let ty = parse_block_type(parser)?;
+            let body = parse_instructions(parser)?;
+            parser.expect_atom("end")?;
+            Instruction::Loop(ty, body)
+        }
+        "if" => {
+            let ty = parse_block_type(parser)?;
+            let then_body = parse_instructions(parser)?;
+            let else_body = if matches!(parser.peek(), Some(Token::Atom(a)) if a == "else") {
+                parser.next_atom()?;
+                parse_instructions(parser)?
+            } else {
+                Vec::new()
+            };
+            parser.expect_atom("end")?;
+            Instruction::If(ty, then_body, else_body)
+        }
+        "br" => Instruction::Br(parser.next_u32()?),
+        "br_if" => Instruction::BrIf(parser.next_u32()?),
+        "return" => Instruction::Return,
+        "call" => Instruction::Call(parser.next_u32()?),
+        "call_indirect" => {
+            parser.expect_open()?;
+            parser.expect_atom("type")?;
+            let type_index = parser.next_u32()?;
+            parser.expect_close()?;
+            parser.expect_open()?;
+            parser.expect_atom("table")?;
+            let table_index = parser.next_u32()?;
+            parser.expect_close()?;
+            Instruction::CallIndirect { type_index, table_index }
+        }
+        "br_table" => {
+            let mut nums = Vec::new();
+            while matches!(parser.peek(), Some(Token::Atom(a)) if a.chars().all(|c| c.is_ascii_digit())) {
+                nums.push(parser.next_u32()?);
+            }
+            let default = nums.pop().ok_or_else(|| err("br_table needs at least a default target"))?;
+            Instruction::BrTable { targets: nums, default }
+        }
+        "drop" => Instruction::Drop,
+        "select" => Instruction::Select,
+        "local.get" => Instruction::LocalGet(parser.next_u32()?),
+        "local.set" => Instruction::LocalSet(parser.next_u32()?),
+        "local.tee" => Instruction::LocalTee(parser.next_u32()?),
+        "global.get" => Instruction::GlobalGet(parser.next_u32()?),
+        "global.set" => Instruction::GlobalSet(parser.next_u32()?),
+        "memory.size" => Instruction::MemorySize,
+        "memory.grow" => Instruction::MemoryGrow,
+        "i32.const" => Instruction::I32Const(parser.next_atom()?.parse().map_err(|_| err("bad i32 literal"))?),
+        "i64.const" => Instruction::I64Const(parser.next_atom()?.parse().map_err(|_| err("bad i64 literal"))?),
+        "f32.const" => Instruction::F32Const(parse_float32(&parser.next_atom()?)?),
+        "f64.const" => Instruction::F64Const(parse_float64(&parser.next_atom()?)?),
+        "i32.add" => Instruction::I32Add,
+        "i32.sub" => Instruction::I32Sub,
+        "i32.mul" => Instruction::I32Mul,
+        "i32.load" => Instruction::I32Load(parse_mem_arg(parser)?),
+        "i64.load" => Instruction::I64Load(parse_mem_arg(parser)?),
+        "f32.load" => Instruction::F32Load(parse_mem_arg(parser)?),
+        "f64.load" => Instruction::F64Load(parse_mem_arg(parser)?),
+        "i32.store" => Instruction::I32Store(parse_mem_arg(parser)?),
+        "i64.store" => Instruction::I64Store(parse_mem_arg(parser)?),
+        "f32.store" => Instruction::F32Store(parse_mem_arg(parser)?),
+        "f64.store" => Instruction::F64Store(parse_mem_arg(parser)?),
+        #[cfg(feature = "threads")]
+        other if other.starts_with("memory.atomic.") || other.starts_with("atomic.") || other.starts_with("i32.atomic.") || other.starts_with("i64.atomic.") => {
+            parse_atomic_instruction(parser, other)?
+        }
+        #[cfg(feature = "bulk-memory-operations")]
+        "memory.init" => Instruction::BulkMemory(crate::instructions::bulk_memory::BulkMemoryInstruction::MemoryInit {
+            data_index: parser.next_u32()?,
+        }),
+        #[cfg(feature = "bulk-memory-operations")]
+        "data.drop" => Instruction::BulkMemory(crate::instructions::bulk_memory::BulkMemoryInstruction::DataDrop {
+            data_index: parser.next_u32()?,
+        }),
+        other => return Err(err(format!("unsupported instruction '{other}'"))),
+    })
+}
+
+#[cfg(feature = "threads")]
+fn parse_atomic_instruction(parser: &mut Parser, mnemonic: &str) -> Result<Instruction, WatParseError> {
+    use crate::instructions::threads::{AtomicInstruction, RmwOp, Width};
+
+    let atomic = match mnemonic {
+        "memory.atomic.notify" => AtomicInstruction::Notify(parse_mem_arg(parser)?),
+        "memory.atomic.wait32" => AtomicInstruction::Wait32(parse_mem_arg(parser)?),
+        "memory.atomic.wait64" => AtomicInstruction::Wait64(parse_mem_arg(parser)?),
+        "atomic.fence" => AtomicInstruction::Fence,
+        other => {
+            let (width, rest) = match other.strip_prefix("i32.atomic.") {
+                Some(rest) => (Width::I32, rest),
+                None => match other.strip_prefix("i64.atomic.") {
+                    Some(rest) => (Width::I64, rest),
+                    None => return Err(err(format!("unsupported instruction '{other}'"))),
+                },
+            };
+            if let Some(rest) = rest.strip_prefix("load") {
+                let narrow = parse_narrow_suffix(rest, true)?;
+                AtomicInstruction::Load {
+                    width,
+                    narrow,
+                    arg: parse_mem_arg(parser)?,
+                }
+            } else if let Some(rest) = rest.strip_prefix("store") {
+                let narrow = parse_narrow_suffix(rest, false)?;
+                AtomicInstruction::Store {
+                    width,
+                    narrow,
+                    arg: parse_mem_arg(parser)?,
+                }
+            } else if let Some(rest) = rest.strip_prefix("rmw") {
+                let dot = rest.find('.').ok_or_else(|| err(format!("malformed rmw mnemonic '{other}'")))?;
+                let narrow = parse_narrow_suffix(&rest[..dot], false)?;
+                let op_name = rest[dot + 1..].strip_suffix("_u").unwrap_or(&rest[dot + 1..]);
+                let op = match op_name {
+                    "add" => RmwOp::Add,
+                    "sub" => RmwOp::Sub,
+                    "and" => RmwOp::And,
+                    "or" => RmwOp::Or,
+                    "xor" => RmwOp::Xor,
+                    "xchg" => RmwOp::Xchg,
+                    "cmpxchg" => RmwOp::Cmpxchg,
+                    other => return Err(err(format!("unknown atomic rmw op '{other}'"))),
+                };
+                AtomicInstruction::Rmw {
+                    op,
+                    width,
+                    narrow,
+                    arg: parse_mem_arg(parser)?,
+                }
+            } else {
+                return Err(err(format!("unsupported instruction '{other}'")));
+            }
+        }
+    };
+    Ok(Instruction::Atomic(atomic))
+}
+
+/// Parses the narrowing suffix left after stripping `load`/`store`/`rmw`
+/// from an atomic mnemonic: `""`, `"8"`/`"16"`/`"32"`, optionally followed
+/// by `"_u"` for `load` (`with_u_suffix`), back into a [`Narrow`].
+#[cfg(feature = "threads")]
+fn parse_narrow_suffix(suffix: &str, with_u_suffix: bool) -> Result<Option<crate::instructions::threads::Narrow>, WatParseError> {
+    use crate::instructions::threads::Narrow;
+
+    let digits = if with_u_suffix { suffix.strip_suffix("_u").unwrap_or(suffix) } else { suffix };
+    Ok(match digits {
+        "" => None,
+        "8" => Some(Narrow::W8),
+        "16" => Some(Narrow::W16),
+        "32" => Some(Narrow::W32),
+        other => return Err(err(format!("unknown atomic access width '{other}'"))),
+    })
+}
+
+fn parse_mem_arg(parser: &mut Parser) -> Result<crate::io::MemArg, WatParseError> {
+    let mut offset = 0;
+    let mut align = 0;
+    loop {
+        match parser.peek() {
+            Some(Token::Atom(a)) if a.starts_with("offset=") => {
+                offset = a["offset=".len()..].parse().map_err(|_| err("bad offset"))?;
+                parser.next_atom()?;
+            }
+            Some(Token::Atom(a)) if a.starts_with("align=") => {
+                let bytes: u32 = a["align=".len()..].parse().map_err(|_| err("bad align"))?;
+                align = bytes.trailing_zeros();
+                parser.next_atom()?;
+            }
+            _ => break,
+        }
+    }
+    Ok(crate::io::MemArg { align, offset })
+}
+
+fn parse_float32(s: &str) -> Result<f32, WatParseError> {
+    match parse_special_float_bits(s, 23, 8)? {
+        Some(bits) => Ok(f32::from_bits(bits as u32)),
+        None => s.parse().map_err(|_| err(format!("bad float literal '{s}'"))),
+    }
+}
+
+fn parse_float64(s: &str) -> Result<f64, WatParseError> {
+    match parse_special_float_bits(s, 52, 11)? {
+        Some(bits) => Ok(f64::from_bits(bits)),
+        None => s.parse().map_err(|_| err(format!("bad float literal '{s}'"))),
+    }
+}
+
+/// Parses the forms [`format_float_bits`] can produce that a plain
+/// `str::parse` can't: `inf`, `nan`, `nan:0x<payload>`, and hex-float
+/// notation. Returns `None` for anything else, i.e. an ordinary decimal
+/// literal.
+fn parse_special_float_bits(s: &str, mantissa_bits: u32, exponent_bits: u32) -> Result<Option<u64>, WatParseError> {
+    let (sign_negative, rest) = match s.strip_prefix('-') {
+        Some(rest) => (true, rest),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let sign_bit = if sign_negative { 1u64 << (mantissa_bits + exponent_bits) } else { 0 };
+    let exponent_field_all_ones = (1u64 << exponent_bits) - 1;
+
+    if rest == "inf" {
+        return Ok(Some(sign_bit | (exponent_field_all_ones << mantissa_bits)));
+    }
+    if rest == "nan" {
+        let quiet_bit = 1u64 << (mantissa_bits - 1);
+        return Ok(Some(sign_bit | (exponent_field_all_ones << mantissa_bits) | quiet_bit));
+    }
+    if let Some(payload) = rest.strip_prefix("nan:0x") {
+        let mantissa = u64::from_str_radix(payload, 16).map_err(|_| err(format!("bad NaN payload '{payload}'")))?;
+        return Ok(Some(sign_bit | (exponent_field_all_ones << mantissa_bits) | mantissa));
+    }
+    if let Some(hex) = rest.strip_prefix("0x") {
+        return parse_hex_float_bits(hex, sign_bit, mantissa_bits, exponent_bits).map(Some);
+    }
+    Ok(None)
+}
+
+/// Parses the `1.8p+1` part of a `0x1.8p+1` hex-float literal (the `0x`
+/// prefix already stripped) into a raw bit pattern, combined with a sign
+/// bit the caller already extracted.
+fn parse_hex_float_bits(hex: &str, sign_bit: u64, mantissa_bits: u32, exponent_bits: u32) -> Result<u64, WatParseError> {
+    let bias = (1i64 << (exponent_bits - 1)) - 1;
+    let (significand, exponent_str) = hex
+        .split_once('p')
+        .ok_or_else(|| err(format!("hex float '0x{hex}' is missing its 'p' exponent")))?;
+    let exponent: i64 = exponent_str
+        .parse()
+        .map_err(|_| err(format!("bad hex float exponent in '0x{hex}'")))?;
+    let (whole, frac) = significand.split_once('.').unwrap_or((significand, ""));
+    if whole.is_empty() && frac.is_empty() {
+        return Err(err(format!("hex float '0x{hex}' has no digits")));
+    }
+
+    // The grammar allows an unnormalized integer part of any width (`0x2p0`,
+    // `0x10p0`), not just the conventional single `0` or `1` digit, so the
+    // digits have to be packed into one integer and normalized here rather
+    // than assumed to already have an implicit leading one.
+    let mut digits: u128 = 0;
+    for c in whole.chars().chain(frac.chars()) {
+        let digit = c.to_digit(16).ok_or_else(|| err(format!("bad hex digit '{c}' in '0x{hex}'")))? as u128;
+        digits = (digits << 4) | digit;
+    }
+    if digits == 0 {
+        return Ok(sign_bit);
+    }
+
+    let msb = 127 - digits.leading_zeros() as i64;
+    let unbiased_exponent = msb - 4 * frac.chars().count() as i64 + exponent;
+    let min_unbiased_exponent = 1 - bias;
+
+    // Too small to normalize: encode as subnormal, so the mantissa field has
+    // to carry the digits' implicit leading one explicitly, shifted further
+    // right to make up the difference from the minimum normal exponent.
+    let (exponent_field, shift, mantissa_value) = if unbiased_exponent < min_unbiased_exponent {
+        (
+            0,
+            msb - mantissa_bits as i64 + (min_unbiased_exponent - unbiased_exponent),
+            digits,
+        )
+    } else {
+        (unbiased_exponent + bias, msb - mantissa_bits as i64, digits & ((1u128 << msb) - 1))
+    };
+    let mantissa = if shift <= 0 {
+        mantissa_value.checked_shl((-shift) as u32).unwrap_or(0)
+    } else if shift < 128 {
+        mantissa_value >> shift
+    } else {
+        0
+    } as u64
+        & ((1u64 << mantissa_bits) - 1);
+
+    Ok(sign_bit | ((exponent_field as u64) << mantissa_bits) | mantissa)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::module::Export;
+
+    #[test]
+    fn roundtrips_a_small_module() {
+        let mut module = Module::default();
+        module.types.push(FuncType {
+            params: vec![ValueType::I32, ValueType::I32],
+            results: vec![ValueType::I32],
+        });
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: vec![ValueType::I32],
+            instructions: vec![
+                Instruction::LocalGet(0),
+                Instruction::LocalGet(1),
+                Instruction::I32Store(crate::io::MemArg { align: 2, offset: 8 }),
+                Instruction::I32Const(42),
+            ],
+        });
+        module.exports.push(Export {
+            name: "add".to_owned(),
+            func_index: 0,
+        });
+
+        let wat = module.to_wat();
+        let reparsed = Module::from_wat(&wat).expect("round trip should parse");
+        assert_eq!(module, reparsed);
+    }
+
+    #[test]
+    fn roundtrips_float_constants() {
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![
+                Instruction::F32Const(3.0),
+                Instruction::F32Const(-0.5),
+                Instruction::F32Const(f32::INFINITY),
+                Instruction::F32Const(f32::NEG_INFINITY),
+                Instruction::F32Const(f32::NAN),
+                Instruction::F32Const(f32::from_bits(0x7fc00001)),
+                Instruction::F64Const(std::f64::consts::PI),
+                Instruction::F64Const(f64::MIN_POSITIVE / 2.0),
+            ],
+        });
+
+        let wat = module.to_wat();
+        let reparsed = Module::from_wat(&wat).expect("round trip should parse");
+        for (original, reparsed) in module.code[0].instructions.iter().zip(&reparsed.code[0].instructions) {
+            match (original, reparsed) {
+                (Instruction::F32Const(a), Instruction::F32Const(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+                (Instruction::F64Const(a), Instruction::F64Const(b)) => assert_eq!(a.to_bits(), b.to_bits()),
+                _ => panic!("unexpected instruction shape"),
+            }
+        }
+    }
+
+    #[test]
+    fn formats_hex_floats_and_nan_payloads() {
+        assert_eq!(format_f32(3.0), "0x1.8p+1");
+        assert_eq!(format_f32(1.0), "0x1p+0");
+        assert_eq!(format_f32(-0.5), "-0x1p-1");
+        assert_eq!(format_f32(0.0), "0x0p+0");
+        assert_eq!(format_f32(f32::NAN), "nan");
+        assert_eq!(format_f32(f32::from_bits(0x7fc00001)), "nan:0x400001");
+        assert_eq!(format_f32(f32::INFINITY), "inf");
+        assert_eq!(format_f32(f32::NEG_INFINITY), "-inf");
+    }
+
+    #[test]
+    fn parses_hex_floats_with_an_unnormalized_integer_part() {
+        assert_eq!(parse_float32("0x2p0").unwrap(), 2.0);
+        assert_eq!(parse_float32("0x10p0").unwrap(), 16.0);
+        assert_eq!(parse_float32("0x1.8p+1").unwrap(), 3.0);
+        assert_eq!(parse_float64("0xcp-4").unwrap(), 0.75);
+    }
+
+    #[test]
+    fn roundtrips_nested_control_flow() {
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![Instruction::If(
+                BlockType::Empty,
+                vec![Instruction::Nop],
+                vec![Instruction::Unreachable],
+            )],
+        });
+
+        let wat = module.to_wat();
+        let reparsed = Module::from_wat(&wat).expect("round trip should parse");
+        assert_eq!(module, reparsed);
+    }
+
+    #[test]
+    #[cfg(feature = "threads")]
+    fn roundtrips_atomic_instructions() {
+        use crate::instructions::threads::{AtomicInstruction, Narrow, RmwOp, Width};
+
+        let arg = crate::io::MemArg { align: 2, offset: 4 };
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![
+                Instruction::Atomic(AtomicInstruction::Notify(arg)),
+                Instruction::Atomic(AtomicInstruction::Wait32(arg)),
+                Instruction::Atomic(AtomicInstruction::Wait64(arg)),
+                Instruction::Atomic(AtomicInstruction::Fence),
+                Instruction::Atomic(AtomicInstruction::Load {
+                    width: Width::I32,
+                    narrow: None,
+                    arg,
+                }),
+                Instruction::Atomic(AtomicInstruction::Load {
+                    width: Width::I64,
+                    narrow: Some(Narrow::W32),
+                    arg,
+                }),
+                Instruction::Atomic(AtomicInstruction::Store {
+                    width: Width::I32,
+                    narrow: Some(Narrow::W8),
+                    arg,
+                }),
+                Instruction::Atomic(AtomicInstruction::Rmw {
+                    op: RmwOp::Cmpxchg,
+                    width: Width::I64,
+                    narrow: Some(Narrow::W16),
+                    arg,
+                }),
+                Instruction::Atomic(AtomicInstruction::Rmw {
+                    op: RmwOp::Add,
+                    width: Width::I32,
+                    narrow: None,
+                    arg,
+                }),
+            ],
+        });
+
+        let wat = module.to_wat();
+        let reparsed = Module::from_wat(&wat).expect("round trip should parse");
+        assert_eq!(module, reparsed);
+    }
+
+    #[test]
+    #[cfg(feature = "bulk-memory-operations")]
+    fn roundtrips_bulk_memory_instructions() {
+        use crate::instructions::bulk_memory::BulkMemoryInstruction;
+
+        let mut module = Module::default();
+        module.types.push(FuncType::default());
+        module.funcs.push(0);
+        module.code.push(FuncBody {
+            locals: Vec::new(),
+            instructions: vec![
+                Instruction::BulkMemory(BulkMemoryInstruction::MemoryInit { data_index: 3 }),
+                Instruction::BulkMemory(BulkMemoryInstruction::DataDrop { data_index: 3 }),
+            ],
+        });
+
+        let wat = module.to_wat();
+        let reparsed = Module::from_wat(&wat).expect("round trip should parse");
+        assert_eq!(module, reparsed);
+    }
+}
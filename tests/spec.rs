@@ -123,7 +123,7 @@ fn read_tests_from_file(path: &Path, dest: &mut Vec<Test<WasmTest>>, ignore_malf
 fn read_tests_from_dir(path: &Path, dest: &mut Vec<Test<WasmTest>>, ignore_malformed: bool) {
     for file in read_dir(path)? {
         let path = file?.path();
-        if path.extension().map_or(false, |ext| ext == "wast") {
+        if path.extension().is_some_and(|ext| ext == "wast") {
             read_tests_from_file(&path, dest, ignore_malformed)?;
         }
     }
@@ -160,7 +160,7 @@ fn read_all_tests(path: &Path) -> (Vec<Test<WasmTest>>, bool) {
 }
 
 fn unlazify<T: Visit>(mut wasm: T) -> Result<T, DecodeError> {
-    match wasm.visit_mut(|()| {}) {
+    match wasm.visit_mut(&mut |_: &mut dyn std::any::Any| Ok::<(), std::convert::Infallible>(())) {
         Ok(()) => Ok(wasm),
         Err(err) => match err {
             VisitError::LazyDecode(err) => Err(err),
@@ -189,7 +189,7 @@ fn run_test(test: &WasmTest) {
         // might be because the test uses longer LEB128 form than
         // required. Verify that at least decoding it back produces the
         // same module.
-        let module2 = Module::decode_from(out.as_slice())?;
+        let module2 = Module::decode_from(&mut out.as_slice())?;
         if module != module2 {
             bail!(
                 "Roundtrip mismatch. Old: {:#?}\nNew: {:#?}",